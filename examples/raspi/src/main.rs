@@ -19,51 +19,12 @@ fn main() {
     let now = rtc.get_datetime().unwrap();
 
     println!("It's {}, {} {} 20{:02} {:02}:{:02}:{:02}",
-            weekday_name(now),
+            weekday_name(now.weekday),
             now.day,
-            month_name(now),
+            month_name(now.month),
             now.year,
             now.hours,
             now.minutes,
             now.seconds);
 
 }
-
-// Helper function to get the correct name of the day
-fn weekday_name(datetime: DateTime) -> &'static str  {
-    let mut name = "Sunday";
-    let day = datetime.weekday;
-    match day {
-        0 => {name = "Sunday"}
-        1 => {name = "Monday"}
-        2 => {name = "Tuesday"}
-        3 => {name = "Wednesday"}
-        4 => {name = "Thursday"}
-        5 => {name = "Friday"}
-        6 => {name = "Saturday"}
-        _ => ()
-    }
-    name
-}
-
-// Helper function to get the correct name of the month
-fn month_name(datetime: DateTime) -> &'static str  {
-    let mut name = "January";
-    let month = datetime.month;
-    match month {
-        1 => {name = "January"}
-        2 => {name = "February"}
-        3 => {name = "March"}
-        4 => {name = "April"}
-        5 => {name = "May"}
-        6 => {name = "June"}
-        7 => {name = "July"}
-        8 => {name = "August"}
-        9 => {name = "September"}
-        10 => {name = "October"}
-        11 => {name = "November"}
-        12 => {name = "December"}        
-        _ => ()
-    }
-    name
-}
\ No newline at end of file
@@ -16,6 +16,7 @@ use stm32l4xx_hal::{
     };
 
 use pcf8563::*;
+use pcf8563::{weekday_name, month_name};
 
 use core::fmt::Write;
 
@@ -92,9 +93,9 @@ fn main() -> ! {
         // format the date and time in a long format
         // eg. "Today is Sunday, 4 April 2021 16:43:00"
         writeln!(tx, "Today is {}, {} {} 20{:02} {:02}:{:02}:{:02}\r", 
-                weekday_name(now),
+                weekday_name(now.weekday),
                 now.day, 
-                month_name(now),
+                month_name(now.month),
                 now.year, 
                 now.hours, 
                 now.minutes, 
@@ -107,42 +108,3 @@ fn main() -> ! {
         
     }
 }
-
-// Helper function to get the correct name of the day
-fn weekday_name(datetime: DateTime) -> &'static str  {
-    let mut name = "Sunday";
-    let day = datetime.weekday;
-    match day {
-        0 => {name = "Sunday"}
-        1 => {name = "Monday"}
-        2 => {name = "Tuesday"}
-        3 => {name = "Wednesday"}
-        4 => {name = "Thursday"}
-        5 => {name = "Friday"}
-        6 => {name = "Saturday"}
-        _ => ()
-    }
-    name
-}
-
-// Helper function to get the correct name of the month
-fn month_name(datetime: DateTime) -> &'static str  {
-    let mut name = "January";
-    let month = datetime.month;
-    match month {
-        1 => {name = "January"}
-        2 => {name = "February"}
-        3 => {name = "March"}
-        4 => {name = "April"}
-        5 => {name = "May"}
-        6 => {name = "June"}
-        7 => {name = "July"}
-        8 => {name = "August"}
-        9 => {name = "September"}
-        10 => {name = "October"}
-        11 => {name = "November"}
-        12 => {name = "December"}        
-        _ => ()
-    }
-    name
-}
\ No newline at end of file
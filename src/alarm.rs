@@ -1,18 +1,560 @@
 //! # Alarm
 //! All alarm-related functions will be defined here
 //!
-//! As it is now, setting an alarm component (minutes, hours, day, weekday) enables alarm for this component
-//! TO DO: Keep the enabled/disabled bit when setting the alarm components (minutes, hours, day, weekday)
+//! Setting an alarm component (minutes, hours, day, weekday) keeps its enabled/disabled bit as is.
+//! The `Option`-based setters (`set_alarm_minutes_opt()` etc.) and `set_alarm()` set and enable,
+//! or disable, the components.
 
 use super::{
-    decode_bcd, encode_bcd, hal, BitFlags, Control, Error, Register, DEVICE_ADDRESS, PCF8563,
+    calendar, decode_bcd, encode_bcd, hal, BitFlags, Control, DateTime, Error, InterruptSource,
+    OutOfRangeError, Register, TimerFreq, Weekday, DEVICE_ADDRESS, PCF8563,
 };
-use hal::blocking::i2c::{Write, WriteRead};
+use core::time::Duration;
+use hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
+
+/// Bits of the alarm registers holding data, from the minute alarm to the weekday alarm.
+const ALARM_REGISTER_MASKS: [u8; 4] = [0xff, 0xbf, 0xbf, 0x87];
+
+/// Longest duration accepted by `alarm_in()`, as the day alarm matches again after the shortest month.
+const MAX_ALARM_IN_SECONDS: u64 = 28 * 86_400;
+
+/// Complete alarm setting, a component set to `None` is disabled.
+///
+/// The alarm is triggered when all the enabled components match the current time.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AlarmConfig {
+    /// Minutes [0-59].
+    pub minutes: Option<u8>,
+    /// Hours [0-23].
+    pub hours: Option<u8>,
+    /// Day [1-31].
+    pub day: Option<u8>,
+    /// Weekday [0-6].
+    pub weekday: Option<u8>,
+}
+
+impl AlarmConfig {
+    /// Check if all the enabled components are within their ranges.
+    pub(crate) fn is_valid(&self) -> bool {
+        matches!(self.minutes, None | Some(0..=59))
+            && matches!(self.hours, None | Some(0..=23))
+            && matches!(self.day, None | Some(1..=31))
+            && matches!(self.weekday, None | Some(0..=6))
+    }
+
+    /// Check if the date and time satisfy all the enabled components.
+    ///
+    /// The seconds are ignored. Returns `false` if no component is enabled,
+    /// as the RTC never triggers such an alarm.
+    pub fn matches(&self, datetime: &DateTime) -> bool {
+        let component = |alarm: Option<u8>, value: u8| alarm.is_none() || alarm == Some(value);
+        self.is_enabled()
+            && component(self.minutes, datetime.minutes)
+            && component(self.hours, datetime.hours)
+            && component(self.day, datetime.day)
+            && component(self.weekday, datetime.weekday)
+    }
+
+    /// Check if at least one component is enabled.
+    fn is_enabled(&self) -> bool {
+        self.minutes.is_some()
+            || self.hours.is_some()
+            || self.day.is_some()
+            || self.weekday.is_some()
+    }
+
+    /// Register values, from the minute alarm to the weekday alarm.
+    fn to_registers(self) -> [u8; 4] {
+        [
+            encode_alarm(self.minutes),
+            encode_alarm(self.hours),
+            encode_alarm(self.day),
+            encode_alarm(self.weekday),
+        ]
+    }
+}
+
+/// Component of the alarm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlarmComponent {
+    /// Minutes alarm.
+    Minutes,
+    /// Hours alarm.
+    Hours,
+    /// Day alarm.
+    Day,
+    /// Weekday alarm.
+    Weekday,
+}
+
+impl AlarmComponent {
+    /// All the components, in the order of the registers.
+    pub const ALL: [AlarmComponent; 4] = [
+        AlarmComponent::Minutes,
+        AlarmComponent::Hours,
+        AlarmComponent::Day,
+        AlarmComponent::Weekday,
+    ];
+
+    /// Register of the component.
+    fn register(self) -> u8 {
+        match self {
+            AlarmComponent::Minutes => Register::MINUTE_ALARM,
+            AlarmComponent::Hours => Register::HOUR_ALARM,
+            AlarmComponent::Day => Register::DAY_ALARM,
+            AlarmComponent::Weekday => Register::WEEKDAY_ALARM,
+        }
+    }
+}
+
+/// Handling of the days of the month [29-31], which don't exist in every month.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MonthDayPolicy {
+    /// Only accept the days [1-28], which exist in every month.
+    Strict,
+    /// Also accept the days [29-31], the alarm is not triggered in the months without that day.
+    SkipShortMonths,
+}
+
+impl From<AlarmSettings> for AlarmConfig {
+    fn from(settings: AlarmSettings) -> Self {
+        AlarmConfig {
+            minutes: settings.minutes.enabled_value(),
+            hours: settings.hours.enabled_value(),
+            day: settings.day.enabled_value(),
+            weekday: settings.weekday.enabled_value(),
+        }
+    }
+}
+
+/// Value and enable bit of an alarm component, as stored in the RTC.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlarmSetting {
+    /// Value of the component.
+    pub value: u8,
+    /// Is the alarm enabled for the component?
+    pub enabled: bool,
+}
+
+impl AlarmSetting {
+    /// Decode the register value, keeping only the bits relevant for the component.
+    fn decode(data: u8, mask: u8) -> Self {
+        AlarmSetting {
+            value: decode_bcd(data & mask),
+            enabled: data & BitFlags::AE == 0,
+        }
+    }
+
+    /// The value if the alarm is enabled for the component.
+    fn enabled_value(self) -> Option<u8> {
+        if self.enabled {
+            Some(self.value)
+        } else {
+            None
+        }
+    }
+}
+
+/// Values and enable bits of all the alarm components, as stored in the RTC.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlarmSettings {
+    /// Minutes alarm.
+    pub minutes: AlarmSetting,
+    /// Hours alarm.
+    pub hours: AlarmSetting,
+    /// Day alarm.
+    pub day: AlarmSetting,
+    /// Weekday alarm.
+    pub weekday: AlarmSetting,
+}
+
+impl AlarmSettings {
+    /// Decode the register values, from the minute alarm to the weekday alarm.
+    pub(crate) fn from_registers(data: [u8; 4]) -> Self {
+        AlarmSettings {
+            minutes: AlarmSetting::decode(data[0], 0x7f),
+            hours: AlarmSetting::decode(data[1], 0x3f),
+            day: AlarmSetting::decode(data[2], 0x3f),
+            weekday: AlarmSetting::decode(data[3], 0x07),
+        }
+    }
+}
+
+/// Builder of the alarm setting, created by `PCF8563::alarm()`.
+///
+/// The components which are not given are disabled, the alarm interrupt is left
+/// unchanged unless given. Nothing is written before `apply()`.
+#[derive(Debug)]
+pub struct AlarmBuilder<'a, I2C> {
+    rtc: &'a mut PCF8563<I2C>,
+    alarm: AlarmConfig,
+    interrupt: Option<bool>,
+}
+
+impl<'a, I2C, E> AlarmBuilder<'a, I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Enable the minutes alarm [0-59].
+    pub fn minutes(mut self, minutes: u8) -> Self {
+        self.alarm.minutes = Some(minutes);
+        self
+    }
+
+    /// Enable the hours alarm [0-23].
+    pub fn hours(mut self, hours: u8) -> Self {
+        self.alarm.hours = Some(hours);
+        self
+    }
+
+    /// Enable the day alarm [1-31].
+    pub fn day(mut self, day: u8) -> Self {
+        self.alarm.day = Some(day);
+        self
+    }
+
+    /// Enable the weekday alarm.
+    pub fn weekday(mut self, weekday: Weekday) -> Self {
+        self.alarm.weekday = Some(weekday.into());
+        self
+    }
+
+    /// Enable or disable the alarm interrupt.
+    pub fn interrupt(mut self, enabled: bool) -> Self {
+        self.interrupt = Some(enabled);
+        self
+    }
+
+    /// Write the alarm registers in one transaction, then the alarm interrupt if given.
+    ///
+    /// Will return an 'Error::InvalidInputData' if any of the components is out of range,
+    /// in which case nothing is written.
+    pub fn apply(self) -> Result<(), Error<E>> {
+        self.rtc.set_alarm(&self.alarm)?;
+        match self.interrupt {
+            Some(true) => self.rtc.control_alarm_interrupt(Control::On),
+            Some(false) => self.rtc.control_alarm_interrupt(Control::Off),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Alarm setting and interrupt, as exported by `export_alarm()` for provisioning other devices.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlarmExport {
+    /// Alarm components.
+    pub alarm: AlarmConfig,
+    /// Is the alarm interrupt enabled?
+    pub interrupt_enabled: bool,
+}
+
+/// Complete state of the alarm: values and enable bits of the components, flag and interrupt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlarmStatus {
+    /// Values and enable bits of the alarm components.
+    pub settings: AlarmSettings,
+    /// Is the alarm flag (AF) set?
+    pub flag: bool,
+    /// Is the alarm interrupt (AIE) enabled?
+    pub interrupt_enabled: bool,
+}
+
+/// Timestamp of `alarm_in()`: after the duration, rounded up to the next whole minute,
+/// at least one minute ahead.
+///
+/// Will return an `OutOfRangeError` if the duration is longer than 28 days, or if the alarm
+/// would match earlier, e.g. 28 days ahead in February of a common year.
+pub(crate) fn alarm_in_target(now: i64, duration: Duration) -> Result<i64, OutOfRangeError> {
+    if duration.as_secs() > MAX_ALARM_IN_SECONDS {
+        return Err(OutOfRangeError);
+    }
+    let mut target = now + duration.as_secs() as i64;
+    if duration.subsec_nanos() > 0 {
+        target += 1;
+    }
+    target += (60 - target % 60) % 60;
+    if target <= now {
+        target += 60;
+    }
+    if next_day_alarm(now, target) != Some(target) {
+        return Err(OutOfRangeError);
+    }
+    Ok(target)
+}
+
+/// Timestamp of the first match after `after` of the day, hours and minutes alarms
+/// set to the timestamp `at`.
+fn next_day_alarm(after: i64, at: i64) -> Option<i64> {
+    let day = calendar::civil_from_days(at.div_euclid(calendar::SECONDS_PER_DAY)).2;
+    let time = at.rem_euclid(calendar::SECONDS_PER_DAY);
+    let time = time - time % 60;
+    let today = after.div_euclid(calendar::SECONDS_PER_DAY);
+    (today..=today + 62)
+        .filter(|days| calendar::civil_from_days(*days).2 == day)
+        .map(|days| days * calendar::SECONDS_PER_DAY + time)
+        .find(|timestamp| *timestamp > after)
+}
+
+/// Encode an alarm component, setting the AE bit (alarm disabled) for `None`.
+fn encode_alarm(value: Option<u8>) -> u8 {
+    match value {
+        Some(value) => encode_bcd(value),
+        None => BitFlags::AE,
+    }
+}
 
 impl<I2C, E> PCF8563<I2C>
 where
     I2C: Write<Error = E> + WriteRead<Error = E>,
 {
+    /// Set all the alarm components at once, in a single transaction.
+    ///
+    /// Will return an 'Error::InvalidInputData' if any of the enabled components is out of range.
+    pub fn set_alarm(&mut self, alarm: &AlarmConfig) -> Result<(), Error<E>> {
+        if !alarm.is_valid() {
+            return Err(Error::InvalidInputData);
+        }
+        self.write_alarm_registers(Register::MINUTE_ALARM, &alarm.to_registers())
+    }
+
+    /// Start building the alarm setting, see `AlarmBuilder`.
+    pub fn alarm(&mut self) -> AlarmBuilder<'_, I2C> {
+        AlarmBuilder {
+            rtc: self,
+            alarm: AlarmConfig::default(),
+            interrupt: None,
+        }
+    }
+
+    /// Set the alarm to the given time every day, in a single transaction.
+    ///
+    /// The minutes and hours alarms are enabled, the day and weekday alarms are disabled.
+    ///
+    /// Will return an 'Error::InvalidInputData' if any of the parameters is out of range.
+    pub fn set_alarm_time(&mut self, hours: u8, minutes: u8) -> Result<(), Error<E>> {
+        self.set_alarm(&AlarmConfig {
+            minutes: Some(minutes),
+            hours: Some(hours),
+            day: None,
+            weekday: None,
+        })
+    }
+
+    /// Set the alarm to the given day of the month and time, every month, in a single transaction.
+    ///
+    /// The minutes, hours and day alarms are enabled, the weekday alarm is disabled.
+    ///
+    /// Will return an 'Error::InvalidInputData' if any of the parameters is out of range,
+    /// including the days [29-31] with `MonthDayPolicy::Strict`.
+    pub fn set_monthly_alarm(
+        &mut self,
+        day: u8,
+        hours: u8,
+        minutes: u8,
+        policy: MonthDayPolicy,
+    ) -> Result<(), Error<E>> {
+        if policy == MonthDayPolicy::Strict && day > 28 {
+            return Err(Error::InvalidInputData);
+        }
+        self.set_alarm(&AlarmConfig {
+            minutes: Some(minutes),
+            hours: Some(hours),
+            day: Some(day),
+            weekday: None,
+        })
+    }
+
+    /// Set the alarm to trigger after the given duration, and enable the alarm interrupt.
+    ///
+    /// The alarm has a resolution of one minute, so the alarm time is rounded up to the next
+    /// whole minute (at least one minute ahead). The minutes, hours and day alarms are enabled,
+    /// the weekday alarm is disabled. Returns the date and time at which the alarm will trigger.
+    ///
+    /// Will return an 'Error::InvalidInputData' if the duration is longer than 28 days, or if
+    /// the alarm would trigger earlier, e.g. 28 days ahead in February of a common year.
+    pub fn alarm_in(&mut self, duration: Duration) -> Result<DateTime, Error<E>> {
+        if duration.as_secs() > MAX_ALARM_IN_SECONDS {
+            return Err(Error::InvalidInputData);
+        }
+        let now = self.get_unix_timestamp()?;
+        let target =
+            DateTime::from_unix_timestamp_with(alarm_in_target(now, duration)?, self.year_mapping)?;
+        self.set_alarm_at(&target)?;
+        self.control_alarm_interrupt(Control::On)?;
+        Ok(target)
+    }
+
+    /// Set the minutes, hours and day alarms to the date and time, and disable the weekday alarm.
+    ///
+    /// The seconds are ignored. Will return an 'Error::InvalidInputData' if the date and time
+    /// is invalid or is not the next time the alarm would match, i.e. is in the past or more
+    /// than one month ahead.
+    pub fn set_alarm_from_datetime(&mut self, target: &DateTime) -> Result<(), Error<E>> {
+        let target_timestamp = target.to_unix_timestamp_with(self.year_mapping)?;
+        let now = self.get_unix_timestamp()?;
+        let minute = target_timestamp - i64::from(target.seconds);
+        if next_day_alarm(now, minute) != Some(minute) {
+            return Err(Error::InvalidInputData);
+        }
+        self.set_alarm_at(target)
+    }
+
+    /// Set an alarm with a resolution of one second, combining the alarm and the timer.
+    ///
+    /// Targets up to 255 seconds ahead use the timer at 1 Hz only. Otherwise the alarm is set
+    /// to the minute of the target, and `service_precise_alarm()` starts the timer for the
+    /// remaining seconds when the alarm flag is set. The timer and alarm interrupts are enabled
+    /// as needed. The first period of the timer may be shorter, so the precision is one second.
+    ///
+    /// Will return an 'Error::InvalidInputData' if the target is in the past or more than
+    /// one month ahead.
+    pub fn set_precise_alarm(&mut self, target: &DateTime) -> Result<(), Error<E>> {
+        let target_timestamp = target.to_unix_timestamp_with(self.year_mapping)?;
+        let now = self.get_unix_timestamp()?;
+        let remaining = target_timestamp - now;
+        self.precise_alarm_seconds = None;
+        if remaining <= 0 {
+            return Err(Error::InvalidInputData);
+        }
+        if remaining <= 255 {
+            return self.start_precise_countdown(remaining as u8);
+        }
+        let minute = target_timestamp - i64::from(target.seconds);
+        if next_day_alarm(now, minute) != Some(minute) {
+            return Err(Error::InvalidInputData);
+        }
+        self.set_alarm_at(target)?;
+        self.control_alarm_interrupt(Control::On)?;
+        if target.seconds > 0 {
+            self.precise_alarm_seconds = Some(target.seconds);
+        }
+        Ok(())
+    }
+
+    /// Handle the interrupt of an alarm set with `set_precise_alarm()`, clearing the flags.
+    ///
+    /// Starts the timer for the remaining seconds when the alarm flag is set.
+    /// Returns `true` when the target time is reached.
+    pub fn service_precise_alarm(&mut self) -> Result<bool, Error<E>> {
+        match self.handle_interrupt()? {
+            InterruptSource::None => Ok(false),
+            InterruptSource::Alarm => match self.precise_alarm_seconds.take() {
+                Some(seconds) => {
+                    self.start_precise_countdown(seconds)?;
+                    Ok(false)
+                }
+                None => Ok(true),
+            },
+            InterruptSource::Timer | InterruptSource::Both => {
+                self.precise_alarm_seconds = None;
+                self.control_timer(Control::Off)?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Start the timer at 1 Hz for the number of seconds, with the timer interrupt enabled.
+    fn start_precise_countdown(&mut self, seconds: u8) -> Result<(), Error<E>> {
+        self.control_timer(Control::Off)?;
+        self.set_timer_frequency(TimerFreq::Timer_1Hz)?;
+        self.set_timer(seconds)?;
+        self.clear_timer_flag()?;
+        self.control_timer_interrupt(Control::On)?;
+        self.control_timer(Control::On)
+    }
+
+    /// Set the minutes, hours and day alarms to the date and time, and disable the weekday alarm.
+    pub(crate) fn set_alarm_at(&mut self, target: &DateTime) -> Result<(), Error<E>> {
+        self.set_alarm(&AlarmConfig {
+            minutes: Some(target.minutes),
+            hours: Some(target.hours),
+            day: Some(target.day),
+            weekday: None,
+        })
+    }
+
+    /// Clear the alarm flag and set the alarm again the given number of minutes [1-1440] from now.
+    ///
+    /// The minutes and hours alarms are enabled, and the day and weekday alarms are moved
+    /// along if they are enabled. Returns the date and time at which the alarm will trigger.
+    ///
+    /// Will return an 'Error::InvalidInputData' if the number of minutes is out of range.
+    pub fn snooze(&mut self, minutes: u16) -> Result<DateTime, Error<E>> {
+        if !(1..=1440).contains(&minutes) {
+            return Err(Error::InvalidInputData);
+        }
+        self.clear_alarm_flag()?;
+        let now = self.get_unix_timestamp()?;
+        let target = DateTime::from_unix_timestamp_with(
+            now - now % 60 + i64::from(minutes) * 60,
+            self.year_mapping,
+        )?;
+        let alarm = self.get_alarm()?;
+        self.set_alarm(&AlarmConfig {
+            minutes: Some(target.minutes),
+            hours: Some(target.hours),
+            day: alarm.day.map(|_| target.day),
+            weekday: alarm.weekday.map(|_| target.weekday),
+        })?;
+        Ok(target)
+    }
+
+    /// Check if the date and time satisfy the alarm currently configured in the RTC.
+    ///
+    /// See `AlarmConfig::matches()`.
+    pub fn alarm_matches(&mut self, datetime: &DateTime) -> Result<bool, Error<E>> {
+        Ok(self.get_alarm()?.matches(datetime))
+    }
+
+    /// Read all the alarm components at once, in a single transaction.
+    pub fn get_alarm(&mut self) -> Result<AlarmConfig, Error<E>> {
+        Ok(self.get_alarm_settings()?.into())
+    }
+
+    /// Read the values and enable bits of all the alarm components at once, in a single transaction.
+    ///
+    /// Unlike `get_alarm()`, the values of the disabled components are also returned.
+    pub fn get_alarm_settings(&mut self) -> Result<AlarmSettings, Error<E>> {
+        let mut data = [0; 4];
+        self.read_registers(Register::MINUTE_ALARM, &mut data)?;
+        Ok(AlarmSettings::from_registers(data))
+    }
+
+    /// Read the complete state of the alarm, in two transactions.
+    pub fn alarm_status(&mut self) -> Result<AlarmStatus, Error<E>> {
+        let control = self.read_register(Register::CTRL_STATUS_2)?;
+        Ok(AlarmStatus {
+            settings: self.get_alarm_settings()?,
+            flag: control & BitFlags::AF != 0,
+            interrupt_enabled: control & BitFlags::AIE != 0,
+        })
+    }
+
+    /// Read the alarm setting and interrupt, to be imported into another device.
+    pub fn export_alarm(&mut self) -> Result<AlarmExport, Error<E>> {
+        Ok(AlarmExport {
+            alarm: self.get_alarm()?,
+            interrupt_enabled: self.is_alarm_interrupt_enabled()?,
+        })
+    }
+
+    /// Set the alarm and the alarm interrupt as exported by `export_alarm()`.
+    ///
+    /// Will return an 'Error::InvalidInputData' if any of the alarm components is out of range.
+    pub fn import_alarm(&mut self, export: &AlarmExport) -> Result<(), Error<E>> {
+        self.set_alarm(&export.alarm)?;
+        let status = if export.interrupt_enabled {
+            Control::On
+        } else {
+            Control::Off
+        };
+        self.control_alarm_interrupt(status)
+    }
+
     /// Set the alarm minutes [0-59], keeping the AE bit unchanged.
     pub fn set_alarm_minutes(&mut self, minutes: u8) -> Result<(), Error<E>> {
         if minutes > 59 {
@@ -22,7 +564,7 @@ where
         let data: u8 = data & BitFlags::AE; // keep the AE bit as is
         let setting: u8 = encode_bcd(minutes);
         let data: u8 = data | setting;
-        self.write_register(Register::MINUTE_ALARM, data)
+        self.write_alarm_registers(Register::MINUTE_ALARM, &[data])
     }
 
     /// Set the alarm hours [0-23], keeping the AE bit unchanged.
@@ -34,19 +576,19 @@ where
         let data: u8 = data & BitFlags::AE; // keep the AE bit as is
         let setting: u8 = encode_bcd(hours);
         let data: u8 = data | setting;
-        self.write_register(Register::HOUR_ALARM, data)
+        self.write_alarm_registers(Register::HOUR_ALARM, &[data])
     }
 
     /// Set the alarm day [1-31], keeping the AE bit unchanged.
     pub fn set_alarm_day(&mut self, day: u8) -> Result<(), Error<E>> {
-        if day < 1 || day > 31 {
+        if !(1..=31).contains(&day) {
             return Err(Error::InvalidInputData);
         }
         let data: u8 = self.read_register(Register::DAY_ALARM)?; // read current value
         let data: u8 = data & BitFlags::AE; // keep the AE bit as is
         let setting: u8 = encode_bcd(day);
         let data: u8 = data | setting;
-        self.write_register(Register::DAY_ALARM, data)
+        self.write_alarm_registers(Register::DAY_ALARM, &[data])
     }
 
     /// Set the alarm weekday [0-6], keeping the AE bit unchanged.
@@ -58,67 +600,116 @@ where
         let data: u8 = data & BitFlags::AE; // keep the AE bit as is
         let setting: u8 = encode_bcd(weekday);
         let data: u8 = data | setting;
-        self.write_register(Register::WEEKDAY_ALARM, data)
+        self.write_alarm_registers(Register::WEEKDAY_ALARM, &[data])
+    }
+
+    /// Set and enable the alarm minutes [0-59], or disable them with `None`.
+    pub fn set_alarm_minutes_opt(&mut self, minutes: Option<u8>) -> Result<(), Error<E>> {
+        match minutes {
+            Some(minutes) if minutes > 59 => Err(Error::InvalidInputData),
+            Some(_) => self.write_alarm_registers(Register::MINUTE_ALARM, &[encode_alarm(minutes)]),
+            None => self.control_alarm_minutes(Control::Off),
+        }
+    }
+
+    /// Set and enable the alarm hours [0-23], or disable them with `None`.
+    pub fn set_alarm_hours_opt(&mut self, hours: Option<u8>) -> Result<(), Error<E>> {
+        match hours {
+            Some(hours) if hours > 23 => Err(Error::InvalidInputData),
+            Some(_) => self.write_alarm_registers(Register::HOUR_ALARM, &[encode_alarm(hours)]),
+            None => self.control_alarm_hours(Control::Off),
+        }
+    }
+
+    /// Set and enable the alarm day [1-31], or disable it with `None`.
+    pub fn set_alarm_day_opt(&mut self, day: Option<u8>) -> Result<(), Error<E>> {
+        match day {
+            Some(day) if !(1..=31).contains(&day) => Err(Error::InvalidInputData),
+            Some(_) => self.write_alarm_registers(Register::DAY_ALARM, &[encode_alarm(day)]),
+            None => self.control_alarm_day(Control::Off),
+        }
+    }
+
+    /// Set and enable the alarm weekday [0-6], or disable it with `None`.
+    pub fn set_alarm_weekday_opt(&mut self, weekday: Option<u8>) -> Result<(), Error<E>> {
+        match weekday {
+            Some(weekday) if weekday > 6 => Err(Error::InvalidInputData),
+            Some(_) => {
+                self.write_alarm_registers(Register::WEEKDAY_ALARM, &[encode_alarm(weekday)])
+            }
+            None => self.control_alarm_weekday(Control::Off),
+        }
+    }
+
+    /// Control an alarm component (On: alarm enabled, Off: alarm disabled).
+    pub fn control_alarm(
+        &mut self,
+        component: AlarmComponent,
+        status: Control,
+    ) -> Result<(), Error<E>> {
+        self.control_alarm_register(component.register(), status)
+    }
+
+    /// Is the alarm component enabled?
+    pub fn is_alarm_enabled(&mut self, component: AlarmComponent) -> Result<bool, Error<E>> {
+        let flag = self.is_register_bit_flag_high(component.register(), BitFlags::AE)?;
+        Ok(!flag)
+    }
+
+    /// Is at least one alarm component enabled? Reads all the components in one transaction.
+    pub fn is_any_alarm_component_enabled(&mut self) -> Result<bool, Error<E>> {
+        let settings = self.get_alarm_settings()?;
+        Ok(settings.minutes.enabled
+            || settings.hours.enabled
+            || settings.day.enabled
+            || settings.weekday.enabled)
+    }
+
+    /// Is the alarm armed, i.e. is at least one component enabled and the alarm interrupt enabled?
+    ///
+    /// Useful to check that the interrupt pin will wake up the device before entering deep sleep.
+    pub fn is_alarm_armed(&mut self) -> Result<bool, Error<E>> {
+        Ok(self.is_alarm_interrupt_enabled()? && self.is_any_alarm_component_enabled()?)
     }
 
     /// Control alarm minutes (On: alarm enabled, Off: alarm disabled).
     pub fn control_alarm_minutes(&mut self, status: Control) -> Result<(), Error<E>> {
-        match status {
-            Control::Off => self.set_register_bit_flag(Register::MINUTE_ALARM, BitFlags::AE),
-            Control::On => self.clear_register_bit_flag(Register::MINUTE_ALARM, BitFlags::AE),
-        }
+        self.control_alarm(AlarmComponent::Minutes, status)
     }
 
     /// Is alarm minutes enabled?
     pub fn is_alarm_minutes_enabled(&mut self) -> Result<bool, Error<E>> {
-        let flag = self.is_register_bit_flag_high(Register::MINUTE_ALARM, BitFlags::AE)?;
-        let flag = flag ^ true;
-        Ok(flag)
+        self.is_alarm_enabled(AlarmComponent::Minutes)
     }
 
     /// Control alarm hours (On: alarm enabled, Off: alarm disabled).
     pub fn control_alarm_hours(&mut self, status: Control) -> Result<(), Error<E>> {
-        match status {
-            Control::Off => self.set_register_bit_flag(Register::HOUR_ALARM, BitFlags::AE),
-            Control::On => self.clear_register_bit_flag(Register::HOUR_ALARM, BitFlags::AE),
-        }
+        self.control_alarm(AlarmComponent::Hours, status)
     }
 
     /// Is alarm hours enabled?
     pub fn is_alarm_hours_enabled(&mut self) -> Result<bool, Error<E>> {
-        let flag = self.is_register_bit_flag_high(Register::HOUR_ALARM, BitFlags::AE)?;
-        let flag = flag ^ true;
-        Ok(flag)
+        self.is_alarm_enabled(AlarmComponent::Hours)
     }
 
     /// Control alarm day (On: alarm enabled, Off: alarm disabled).
     pub fn control_alarm_day(&mut self, status: Control) -> Result<(), Error<E>> {
-        match status {
-            Control::Off => self.set_register_bit_flag(Register::DAY_ALARM, BitFlags::AE),
-            Control::On => self.clear_register_bit_flag(Register::DAY_ALARM, BitFlags::AE),
-        }
+        self.control_alarm(AlarmComponent::Day, status)
     }
 
     /// Is alarm day enabled?
     pub fn is_alarm_day_enabled(&mut self) -> Result<bool, Error<E>> {
-        let flag = self.is_register_bit_flag_high(Register::DAY_ALARM, BitFlags::AE)?;
-        let flag = flag ^ true;
-        Ok(flag)
+        self.is_alarm_enabled(AlarmComponent::Day)
     }
 
     /// Control alarm weekday (On: alarm enabled, Off: alarm disabled).
     pub fn control_alarm_weekday(&mut self, status: Control) -> Result<(), Error<E>> {
-        match status {
-            Control::Off => self.set_register_bit_flag(Register::WEEKDAY_ALARM, BitFlags::AE),
-            Control::On => self.clear_register_bit_flag(Register::WEEKDAY_ALARM, BitFlags::AE),
-        }
+        self.control_alarm(AlarmComponent::Weekday, status)
     }
 
     /// Is alarm weekday enabled?
     pub fn is_alarm_weekday_enabled(&mut self) -> Result<bool, Error<E>> {
-        let flag = self.is_register_bit_flag_high(Register::WEEKDAY_ALARM, BitFlags::AE)?;
-        let flag = flag ^ true;
-        Ok(flag)
+        self.is_alarm_enabled(AlarmComponent::Weekday)
     }
 
     /// Enable or disable alarm interrupt.
@@ -175,17 +766,537 @@ where
         self.clear_register_bit_flag(Register::CTRL_STATUS_2, BitFlags::AF)
     }
 
+    /// Wait until the alarm flag is set, polling it at the given interval, and clear it.
+    ///
+    /// For applications not using the interrupt pin. Blocks forever if the alarm is not set.
+    pub fn wait_for_alarm<D>(
+        &mut self,
+        delay: &mut D,
+        poll_interval_ms: u32,
+    ) -> Result<(), Error<E>>
+    where
+        D: DelayMs<u32>,
+    {
+        while !self.get_alarm_flag()? {
+            delay.delay_ms(poll_interval_ms);
+        }
+        self.clear_alarm_flag()
+    }
+
     /// Check if alarm interrupt is enabled.
     pub fn is_alarm_interrupt_enabled(&mut self) -> Result<bool, Error<E>> {
         self.is_register_bit_flag_high(Register::CTRL_STATUS_2, BitFlags::AIE)
     }
 
-    /// Shut off the alarms at once.
+    /// Shut off the alarms at once, keeping the alarm values (one read and one write).
     pub fn disable_all_alarms(&mut self) -> Result<(), Error<E>> {
-        self.control_alarm_minutes(Control::Off)?;
-        self.control_alarm_hours(Control::Off)?;
-        self.control_alarm_day(Control::Off)?;
-        self.control_alarm_weekday(Control::Off)?;
-        Ok(())
+        let mut data = [0; 4];
+        self.read_registers(Register::MINUTE_ALARM, &mut data)?;
+        for register in data.iter_mut() {
+            *register |= BitFlags::AE;
+        }
+        self.write_alarm_registers(Register::MINUTE_ALARM, &data)
+    }
+
+    /// Enable or disable the verification of the alarm register writes.
+    ///
+    /// When enabled, the alarm registers are read back after every write, and an
+    /// `Error::VerificationFailed` is returned if they don't hold the written values.
+    pub fn control_alarm_verification(&mut self, status: Control) {
+        self.verify_alarm_writes = matches!(status, Control::On);
+    }
+
+    /// Is the verification of the alarm register writes enabled?
+    pub fn is_alarm_verification_enabled(&self) -> bool {
+        self.verify_alarm_writes
+    }
+
+    /// Set or clear the AE bit of an alarm register (On: alarm enabled, Off: alarm disabled).
+    fn control_alarm_register(&mut self, register: u8, status: Control) -> Result<(), Error<E>> {
+        let data = self.read_register(register)?;
+        let new = match status {
+            Control::On => data & !BitFlags::AE,
+            Control::Off => data | BitFlags::AE,
+        };
+        if new == data {
+            return Ok(());
+        }
+        self.write_alarm_registers(register, &[new])
+    }
+
+    /// Write to consecutive alarm registers, reading them back if the verification is enabled.
+    fn write_alarm_registers(&mut self, register: u8, data: &[u8]) -> Result<(), Error<E>> {
+        self.write_registers(register, data)?;
+        if !self.verify_alarm_writes {
+            return Ok(());
+        }
+        let mut read = [0; 4];
+        let read = &mut read[..data.len()];
+        self.read_registers(register, read)?;
+        let masks = &ALARM_REGISTER_MASKS[usize::from(register - Register::MINUTE_ALARM)..];
+        let matches = data
+            .iter()
+            .zip(read.iter())
+            .zip(masks)
+            .all(|((written, read), mask)| (written ^ read) & mask == 0);
+        if matches {
+            Ok(())
+        } else {
+            Err(Error::VerificationFailed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::YearMapping;
+    use embedded_hal_mock::{DelayMockNoop, I2cMock};
+
+    #[test]
+    fn can_set_alarm() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        let alarm = AlarmConfig {
+            minutes: Some(25),
+            hours: Some(9),
+            day: None,
+            weekday: Some(1),
+        };
+        rtc.set_alarm(&alarm).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[0x09, 0x25, 0x09, 0x80, 0x01]);
+    }
+
+    #[test]
+    fn can_set_alarm_time() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        rtc.set_alarm_time(23, 59).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[0x09, 0x59, 0x23, 0x80, 0x80]);
+    }
+
+    #[test]
+    fn can_set_and_enable_alarm_component() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        rtc.set_alarm_hours_opt(Some(21)).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[0x0A, 0x21]);
+    }
+
+    #[test]
+    fn can_disable_alarm_component() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x21]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.set_alarm_hours_opt(None).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[0x0A, 0xA1]);
+    }
+
+    #[test]
+    fn can_set_monthly_alarm() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        rtc.set_monthly_alarm(31, 6, 30, MonthDayPolicy::SkipShortMonths)
+            .unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[0x09, 0x30, 0x06, 0x31, 0x80]);
+    }
+
+    #[test]
+    fn cannot_set_monthly_alarm_on_short_month_day_when_strict() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        assert!(rtc
+            .set_monthly_alarm(29, 6, 30, MonthDayPolicy::Strict)
+            .is_err());
+        assert!(rtc
+            .set_monthly_alarm(28, 6, 30, MonthDayPolicy::Strict)
+            .is_ok());
+    }
+
+    #[test]
+    fn can_set_alarm_in_duration_across_month() {
+        let mut i2c = I2cMock::new();
+        // 2021-04-30 23:59:30
+        i2c.set_read_data(&[0x30, 0x59, 0x23, 0x30, 0x05, 0x04, 0x21]);
+        let mut rtc = PCF8563::new(i2c);
+        let target = rtc.alarm_in(Duration::from_secs(45)).unwrap();
+        assert_eq!((target.month, target.day), (5, 1));
+        assert_eq!((target.hours, target.minutes, target.seconds), (0, 1, 0));
+    }
+
+    #[test]
+    fn cannot_set_alarm_in_matching_earlier() {
+        let mut i2c = I2cMock::new();
+        // 2021-02-01 12:00:30
+        i2c.set_read_data(&[0x30, 0x00, 0x12, 0x01, 0x01, 0x02, 0x21]);
+        let mut rtc = PCF8563::new(i2c);
+        assert!(matches!(
+            rtc.alarm_in(Duration::from_secs(28 * 86_400)),
+            Err(Error::InvalidInputData)
+        ));
+        let target = rtc.alarm_in(Duration::from_secs(27 * 86_400)).unwrap();
+        assert_eq!((target.month, target.day), (2, 28));
+    }
+
+    #[test]
+    fn alarm_in_uses_year_mapping() {
+        let mut i2c = I2cMock::new();
+        // 2100-02-28 23:59:30, 2100 is not a leap year
+        i2c.set_read_data(&[0x30, 0x59, 0x23, 0x28, 0x00, 0x02, 0x00]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.set_year_mapping(YearMapping::new(2100, 100).unwrap());
+        let target = rtc.alarm_in(Duration::from_secs(45)).unwrap();
+        assert_eq!((target.month, target.day), (3, 1));
+    }
+
+    #[test]
+    fn alarm_in_is_at_least_one_minute_ahead() {
+        let mut i2c = I2cMock::new();
+        // 2021-04-30 12:00:00
+        i2c.set_read_data(&[0x00, 0x00, 0x12, 0x30, 0x05, 0x04, 0x21]);
+        let mut rtc = PCF8563::new(i2c);
+        let target = rtc.alarm_in(Duration::from_secs(0)).unwrap();
+        assert_eq!((target.hours, target.minutes), (12, 1));
+    }
+
+    #[test]
+    fn can_snooze_across_midnight() {
+        let mut i2c = I2cMock::new();
+        // control/status 2 with AF, 2021-04-04 23:55:10, alarm 23:55 on Sunday
+        i2c.set_read_data(&[
+            0x0a, 0x10, 0x55, 0x23, 0x04, 0x00, 0x04, 0x21, 0x55, 0x23, 0x80, 0x00,
+        ]);
+        let mut rtc = PCF8563::new(i2c);
+        let target = rtc.snooze(10).unwrap();
+        assert_eq!((target.day, target.hours, target.minutes), (5, 0, 5));
+        let i2c = rtc.destroy();
+        assert_eq!(
+            i2c.get_write_data(),
+            &[Register::MINUTE_ALARM, 0x05, 0x00, 0x80, 0x01]
+        );
+    }
+
+    #[test]
+    fn cannot_snooze_out_of_range() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        assert!(matches!(rtc.snooze(0), Err(Error::InvalidInputData)));
+        assert!(matches!(rtc.snooze(1441), Err(Error::InvalidInputData)));
+    }
+
+    #[test]
+    fn clearing_alarm_flag_keeps_timer_flag() {
+        let mut i2c = I2cMock::new();
+        // AF and AIE set, TF not set yet
+        i2c.set_read_data(&[0x0a]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.clear_alarm_flag().unwrap();
+        let i2c = rtc.destroy();
+        // TF written as 1, so it is not cleared if set in the meantime
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x06]);
+    }
+
+    #[test]
+    fn can_read_alarm_status() {
+        let mut i2c = I2cMock::new();
+        // AF and AIE set, alarm 07:30 every day
+        i2c.set_read_data(&[0x0a, 0x30, 0x07, 0x81, 0x83]);
+        let mut rtc = PCF8563::new(i2c);
+        let status = rtc.alarm_status().unwrap();
+        assert!(status.flag && status.interrupt_enabled);
+        assert_eq!(
+            AlarmConfig::from(status.settings),
+            AlarmConfig {
+                minutes: Some(30),
+                hours: Some(7),
+                ..Default::default()
+            }
+        );
+        assert_eq!(status.settings.weekday.value, 3);
+    }
+
+    #[test]
+    fn can_wait_for_alarm() {
+        let mut i2c = I2cMock::new();
+        // AF set on the third poll
+        i2c.set_read_data(&[0x02, 0x02, 0x0a, 0x0a]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.wait_for_alarm(&mut DelayMockNoop, 100).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x06]);
+    }
+
+    const APRIL_30: [u8; 7] = [0x00, 0x00, 0x12, 0x30, 0x05, 0x04, 0x21];
+
+    #[test]
+    fn can_set_alarm_from_datetime() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&APRIL_30);
+        let mut rtc = PCF8563::new(i2c);
+        let target = DateTime {
+            year: 21,
+            month: 5,
+            day: 29,
+            weekday: 6,
+            hours: 8,
+            minutes: 15,
+            seconds: 0,
+        };
+        rtc.set_alarm_from_datetime(&target).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[0x09, 0x15, 0x08, 0x29, 0x80]);
+    }
+
+    #[test]
+    fn cannot_set_alarm_from_datetime_too_far_ahead() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&APRIL_30);
+        let mut rtc = PCF8563::new(i2c);
+        // 2021-05-30 would match on 2021-04-30 first
+        let target = DateTime {
+            year: 21,
+            month: 5,
+            day: 30,
+            weekday: 0,
+            hours: 13,
+            minutes: 0,
+            seconds: 0,
+        };
+        assert!(matches!(
+            rtc.set_alarm_from_datetime(&target),
+            Err(Error::InvalidInputData)
+        ));
+    }
+
+    #[test]
+    fn verified_alarm_write_detects_mismatch() {
+        let mut i2c = I2cMock::new();
+        // read back with the hours corrupted
+        i2c.set_read_data(&[0x30, 0x06, 0x80, 0x80]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.control_alarm_verification(Control::On);
+        assert!(matches!(
+            rtc.set_alarm_time(7, 30),
+            Err(Error::VerificationFailed)
+        ));
+    }
+
+    #[test]
+    fn verified_alarm_write_succeeds() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x30, 0x07, 0x80, 0x80]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.control_alarm_verification(Control::On);
+        rtc.set_alarm_time(7, 30).unwrap();
+    }
+
+    #[test]
+    fn can_disable_all_alarms_in_one_write() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x30, 0x07, 0x81, 0x03]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.disable_all_alarms().unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(
+            i2c.get_write_data(),
+            &[Register::MINUTE_ALARM, 0xb0, 0x87, 0x81, 0x83]
+        );
+    }
+
+    #[test]
+    fn can_export_alarm() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x30, 0x07, 0x80, 0x81, 0x02]);
+        let mut rtc = PCF8563::new(i2c);
+        let export = rtc.export_alarm().unwrap();
+        assert_eq!(export.alarm.hours, Some(7));
+        assert!(export.interrupt_enabled);
+    }
+
+    #[test]
+    fn can_import_alarm() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        let export = AlarmExport {
+            alarm: AlarmConfig {
+                minutes: Some(30),
+                hours: Some(7),
+                ..Default::default()
+            },
+            interrupt_enabled: true,
+        };
+        rtc.import_alarm(&export).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x0e]);
+    }
+
+    #[test]
+    fn precise_alarm_uses_alarm_then_timer() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&APRIL_30);
+        let mut rtc = PCF8563::new(i2c);
+        let target = DateTime {
+            year: 21,
+            month: 4,
+            day: 30,
+            weekday: 5,
+            hours: 13,
+            minutes: 0,
+            seconds: 42,
+        };
+        rtc.set_precise_alarm(&target).unwrap();
+        assert_eq!(rtc.precise_alarm_seconds, Some(42));
+
+        let mut i2c = rtc.destroy();
+        // AF set
+        i2c.set_read_data(&[0x0a]);
+        let mut rtc = PCF8563 {
+            precise_alarm_seconds: Some(42),
+            ..PCF8563::new(i2c)
+        };
+        assert!(!rtc.service_precise_alarm().unwrap());
+        assert_eq!(rtc.precise_alarm_seconds, None);
+        let i2c = rtc.destroy();
+        // timer started
+        assert_eq!(i2c.get_write_data(), &[Register::TIMER_CTRL, 0x80]);
+    }
+
+    #[test]
+    fn precise_alarm_uses_timer_only_when_close() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&APRIL_30);
+        let mut rtc = PCF8563::new(i2c);
+        let target = DateTime {
+            year: 21,
+            month: 4,
+            day: 30,
+            weekday: 5,
+            hours: 12,
+            minutes: 2,
+            seconds: 5,
+        };
+        rtc.set_precise_alarm(&target).unwrap();
+        assert_eq!(rtc.precise_alarm_seconds, None);
+    }
+
+    #[test]
+    fn can_control_alarm_component() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x07]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.control_alarm(AlarmComponent::Hours, Control::Off)
+            .unwrap();
+        let mut i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::HOUR_ALARM, 0x87]);
+        i2c.set_read_data(&[0x87]);
+        let mut rtc = PCF8563::new(i2c);
+        assert!(!rtc.is_alarm_enabled(AlarmComponent::Hours).unwrap());
+    }
+
+    #[test]
+    fn alarm_is_armed_with_component_and_interrupt() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x02, 0x80, 0x80, 0x15, 0x80]);
+        let mut rtc = PCF8563::new(i2c);
+        assert!(rtc.is_alarm_armed().unwrap());
+        let mut i2c = rtc.destroy();
+        i2c.set_read_data(&[0x02, 0x80, 0x80, 0x80, 0x80]);
+        let mut rtc = PCF8563::new(i2c);
+        assert!(!rtc.is_alarm_armed().unwrap());
+    }
+
+    #[test]
+    fn can_build_alarm() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        rtc.alarm()
+            .minutes(25)
+            .hours(9)
+            .weekday(Weekday::Monday)
+            .apply()
+            .unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[0x09, 0x25, 0x09, 0x80, 0x01]);
+    }
+
+    #[test]
+    fn cannot_build_invalid_alarm() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        assert!(matches!(
+            rtc.alarm().hours(24).interrupt(true).apply(),
+            Err(Error::InvalidInputData)
+        ));
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[]);
+    }
+
+    #[test]
+    fn alarm_matches_enabled_components() {
+        let datetime = DateTime {
+            year: 21,
+            month: 4,
+            day: 5,
+            weekday: 1,
+            hours: 7,
+            minutes: 30,
+            seconds: 12,
+        };
+        let alarm = AlarmConfig {
+            minutes: Some(30),
+            hours: Some(7),
+            ..Default::default()
+        };
+        assert!(alarm.matches(&datetime));
+        let alarm = AlarmConfig {
+            weekday: Some(2),
+            ..alarm
+        };
+        assert!(!alarm.matches(&datetime));
+        assert!(!AlarmConfig::default().matches(&datetime));
+    }
+
+    #[test]
+    fn cannot_set_invalid_alarm() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        let alarm = AlarmConfig {
+            day: Some(0),
+            ..Default::default()
+        };
+        assert!(rtc.set_alarm(&alarm).is_err());
+    }
+
+    #[test]
+    fn can_get_alarm_settings() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x25, 0x49, 0x91, 0x7e]);
+        let mut rtc = PCF8563::new(i2c);
+        let settings = rtc.get_alarm_settings().unwrap();
+        assert_eq!(
+            settings.day,
+            AlarmSetting {
+                value: 11,
+                enabled: false
+            }
+        );
+        assert_eq!(
+            settings.hours,
+            AlarmSetting {
+                value: 9,
+                enabled: true
+            }
+        );
+    }
+
+    #[test]
+    fn can_get_alarm() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x25, 0x49, 0x80, 0x7e]);
+        let mut rtc = PCF8563::new(i2c);
+        assert_eq!(
+            rtc.get_alarm().unwrap(),
+            AlarmConfig {
+                minutes: Some(25),
+                hours: Some(9),
+                day: None,
+                weekday: Some(6),
+            }
+        );
     }
 }
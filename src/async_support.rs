@@ -0,0 +1,245 @@
+//! Functions awaiting the interrupt pin (requires the `async` feature).
+//!
+//! The pin is any `embedded-hal-async` `Wait` input connected to the open drain INT output,
+//! which is active low.
+
+use super::{hal, BitFlags, Error, InterruptSource, Register, TimerFreq, PCF8563};
+use embedded_hal_async::digital::Wait;
+use hal::blocking::i2c::{Write, WriteRead};
+
+/// Cause of the interrupt, as returned by `next_event()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The alarm flag was set.
+    Alarm,
+    /// The timer flag was set.
+    Timer,
+    /// Both the alarm and the timer flags were set.
+    Both,
+}
+
+impl From<Event> for InterruptSource {
+    fn from(event: Event) -> Self {
+        match event {
+            Event::Alarm => InterruptSource::Alarm,
+            Event::Timer => InterruptSource::Timer,
+            Event::Both => InterruptSource::Both,
+        }
+    }
+}
+
+impl<I2C, E> PCF8563<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Wait until the interrupt pin is low with the timer flag set, and clear the flag.
+    ///
+    /// The alarm interrupt should be disabled, as the pin stays low until the alarm flag
+    /// is cleared: if the pin is low because of the alarm, returns an `Error::AlarmPending`
+    /// when the alarm interrupt is enabled, otherwise waits for the pin to be released and
+    /// low again. Will return an `Error::Pin` if the pin can't be read.
+    pub async fn wait_for_timer_interrupt<P>(&mut self, int: &mut P) -> Result<(), Error<E>>
+    where
+        P: Wait,
+    {
+        loop {
+            int.wait_for_low().await.map_err(|_| Error::Pin)?;
+            let status = self.read_register(Register::CTRL_STATUS_2)?;
+            if status & BitFlags::TF != 0 {
+                return self.clear_register_bit_flag(Register::CTRL_STATUS_2, BitFlags::TF);
+            }
+            if status & (BitFlags::AF | BitFlags::AIE) == BitFlags::AF | BitFlags::AIE {
+                return Err(Error::AlarmPending);
+            }
+            int.wait_for_high().await.map_err(|_| Error::Pin)?;
+        }
+    }
+
+    /// Wait until the interrupt pin is low, then find out which flags are set and clear them
+    /// with `handle_interrupt()`.
+    ///
+    /// If no flag is set, e.g. because of a glitch, waits for the pin to be released and low again.
+    /// Will return an `Error::Pin` if the pin can't be read.
+    pub async fn next_event<P>(&mut self, int: &mut P) -> Result<Event, Error<E>>
+    where
+        P: Wait,
+    {
+        loop {
+            int.wait_for_low().await.map_err(|_| Error::Pin)?;
+            match self.handle_interrupt()? {
+                InterruptSource::Alarm => return Ok(Event::Alarm),
+                InterruptSource::Timer => return Ok(Event::Timer),
+                InterruptSource::Both => return Ok(Event::Both),
+                InterruptSource::None => (),
+            }
+            int.wait_for_high().await.map_err(|_| Error::Pin)?;
+        }
+    }
+}
+
+/// Periodic ticks driven by the timer and the interrupt pin, e.g. to run a task on RTC time
+/// instead of the MCU clock.
+///
+/// ```rust,ignore
+/// let mut ticker = TimerTicker::start(&mut rtc, int_pin, TimerFreq::Timer_1Hz, 1)?;
+/// loop {
+///     ticker.next_tick().await?;
+///     // every second
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TimerTicker<'a, I2C, P> {
+    rtc: &'a mut PCF8563<I2C>,
+    int: P,
+}
+
+impl<'a, I2C, E, P> TimerTicker<'a, I2C, P>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+    P: Wait,
+{
+    /// Start the timer repeating every number of ticks [1-255] at the frequency,
+    /// see `start_periodic()`.
+    pub fn start(
+        rtc: &'a mut PCF8563<I2C>,
+        int: P,
+        frequency: TimerFreq,
+        ticks: u8,
+    ) -> Result<Self, Error<E>> {
+        rtc.start_periodic(frequency, ticks)?;
+        Ok(TimerTicker { rtc, int })
+    }
+
+    /// Wait for the next tick, clearing the timer flag.
+    pub async fn next_tick(&mut self) -> Result<(), Error<E>> {
+        self.rtc.wait_for_timer_interrupt(&mut self.int).await
+    }
+
+    /// Stop the timer, clear the timer flag and disable the timer interrupt.
+    ///
+    /// Returns the interrupt pin.
+    pub fn stop(self) -> Result<P, Error<E>> {
+        self.rtc.stop_and_clear_timer(true)?;
+        Ok(self.int)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+    use embedded_hal_1::digital::ErrorType;
+    use embedded_hal_mock::I2cMock;
+
+    /// Interrupt pin which is always low, counting the waits for the high level.
+    struct LowPin {
+        released: usize,
+    }
+
+    impl ErrorType for LowPin {
+        type Error = Infallible;
+    }
+
+    impl Wait for LowPin {
+        async fn wait_for_high(&mut self) -> Result<(), Infallible> {
+            self.released += 1;
+            Ok(())
+        }
+        async fn wait_for_low(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+        async fn wait_for_rising_edge(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+        async fn wait_for_falling_edge(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+        async fn wait_for_any_edge(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    const NOOP_VTABLE: RawWakerVTable = RawWakerVTable::new(noop_clone, noop, noop, noop);
+
+    fn noop_clone(_: *const ()) -> RawWaker {
+        RawWaker::new(core::ptr::null(), &NOOP_VTABLE)
+    }
+
+    fn noop(_: *const ()) {}
+
+    /// Waker doing nothing, as the futures of the tests never wait.
+    fn noop_waker() -> Waker {
+        // the functions of the vtable do nothing, the null data pointer is never used
+        #[allow(unsafe_code)]
+        unsafe {
+            Waker::from_raw(noop_clone(core::ptr::null()))
+        }
+    }
+
+    /// Run a future which never waits to completion.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let waker = noop_waker();
+        let mut context = Context::from_waker(&waker);
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future is pending"),
+        }
+    }
+
+    #[test]
+    fn next_event_clears_set_flags() {
+        let mut i2c = I2cMock::new();
+        // no flag, then AF, TF and AIE set
+        i2c.set_read_data(&[0x00, 0x0e]);
+        let mut rtc = PCF8563::new(i2c);
+        let mut int = LowPin { released: 0 };
+        assert_eq!(block_on(rtc.next_event(&mut int)).unwrap(), Event::Both);
+        assert_eq!(int.released, 1);
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x02]);
+    }
+
+    #[test]
+    fn waits_for_timer_flag() {
+        let mut i2c = I2cMock::new();
+        // AF set, then TF set
+        i2c.set_read_data(&[0x08, 0x04, 0x04]);
+        let mut rtc = PCF8563::new(i2c);
+        let mut int = LowPin { released: 0 };
+        block_on(rtc.wait_for_timer_interrupt(&mut int)).unwrap();
+        assert_eq!(int.released, 1);
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x08]);
+    }
+
+    #[test]
+    fn fails_if_alarm_holds_pin() {
+        let mut i2c = I2cMock::new();
+        // AF and AIE set
+        i2c.set_read_data(&[0x0a]);
+        let mut rtc = PCF8563::new(i2c);
+        let mut int = LowPin { released: 0 };
+        assert!(matches!(
+            block_on(rtc.wait_for_timer_interrupt(&mut int)),
+            Err(Error::AlarmPending)
+        ));
+        assert_eq!(int.released, 0);
+    }
+
+    #[test]
+    fn can_tick() {
+        let mut i2c = I2cMock::new();
+        // start, then TF set twice
+        i2c.set_read_data(&[0x00, 0x04, 0x04, 0x04, 0x04]);
+        let mut rtc = PCF8563::new(i2c);
+        let int = LowPin { released: 0 };
+        let mut ticker = TimerTicker::start(&mut rtc, int, TimerFreq::Timer_1Hz, 1).unwrap();
+        block_on(ticker.next_tick()).unwrap();
+        block_on(ticker.next_tick()).unwrap();
+        assert_eq!(ticker.stop().unwrap().released, 0);
+    }
+}
@@ -0,0 +1,130 @@
+//! Command line tool to read and set the PCF8563 from Linux, similar to `hwclock`
+//! (requires the `cli` feature).
+//!
+//! ```text
+//! pcf8563 [--bus /dev/i2c-1] get
+//! pcf8563 [--bus /dev/i2c-1] set 2021-04-04T07:15:42
+//! pcf8563 [--bus /dev/i2c-1] sync-to-sys
+//! pcf8563 [--bus /dev/i2c-1] sync-from-sys
+//! pcf8563 [--bus /dev/i2c-1] dump
+//! ```
+//!
+//! The RTC is expected to hold the UTC time, with the years 2000-2099.
+//! Setting the system clock with `sync-to-sys` requires root privileges.
+
+use linux_embedded_hal::I2cdev;
+use pcf8563::{DateTime, YearMapping, PCF8563};
+use std::{env, process};
+
+const DEFAULT_BUS: &str = "/dev/i2c-1";
+
+const USAGE: &str =
+    "usage: pcf8563 [--bus <device>] get|set <YYYY-MM-DDTHH:MM:SS>|sync-to-sys|sync-from-sys|dump";
+
+fn main() {
+    if let Err(message) = run(env::args().skip(1).collect()) {
+        eprintln!("pcf8563: {}", message);
+        process::exit(1);
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+    let mut args = args.iter().map(String::as_str);
+    let mut bus = DEFAULT_BUS;
+    let mut command = args.next().ok_or(USAGE)?;
+    if command == "--bus" {
+        bus = args.next().ok_or(USAGE)?;
+        command = args.next().ok_or(USAGE)?;
+    }
+    let i2c = I2cdev::new(bus).map_err(|e| format!("can't open {}: {}", bus, e))?;
+    let mut rtc = PCF8563::new(i2c);
+    match (command, args.next(), args.next()) {
+        ("get", None, None) => {
+            if rtc.get_voltage_low_flag().map_err(i2c_error)? {
+                eprintln!("pcf8563: warning: voltage low flag set, the time may be invalid");
+            }
+            let datetime = rtc.get_datetime().map_err(i2c_error)?;
+            println!("{}", format_datetime(&datetime));
+        }
+        ("set", Some(datetime), None) => {
+            let datetime = parse_datetime(datetime)?;
+            rtc.set_datetime_and_start(&datetime).map_err(i2c_error)?;
+        }
+        ("sync-to-sys", None, None) => {
+            let timestamp = rtc.get_unix_timestamp().map_err(i2c_error)?;
+            set_system_clock(timestamp)?;
+        }
+        ("sync-from-sys", None, None) => {
+            rtc.sync_from_system_clock().map_err(i2c_error)?;
+        }
+        ("dump", None, None) => {
+            let snapshot = rtc.snapshot().map_err(i2c_error)?;
+            for (address, value) in snapshot.registers().iter().enumerate() {
+                println!("0x{:02x}: 0x{:02x} 0b{:08b}", address, value, value);
+            }
+        }
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
+
+fn i2c_error<E: core::fmt::Debug>(error: pcf8563::Error<E>) -> String {
+    format!("{:?}", error)
+}
+
+fn format_datetime(datetime: &DateTime) -> String {
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        YearMapping::default()
+            .full_year(datetime.year)
+            .unwrap_or_default(),
+        datetime.month,
+        datetime.day,
+        datetime.hours,
+        datetime.minutes,
+        datetime.seconds
+    )
+}
+
+/// Parse `YYYY-MM-DDTHH:MM:SS`, a trailing `Z` is accepted.
+fn parse_datetime(text: &str) -> Result<DateTime, String> {
+    let invalid = || format!("invalid date and time: {}", text);
+    let text = text.strip_suffix('Z').unwrap_or(text);
+    let (date, time) = text.split_once('T').ok_or_else(invalid)?;
+    let date: Vec<&str> = date.split('-').collect();
+    let time: Vec<&str> = time.split(':').collect();
+    if date.len() != 3 || time.len() != 3 {
+        return Err(invalid());
+    }
+    let number = |field: &str| field.parse::<u8>().map_err(|_| invalid());
+    let year = date[0].parse::<i32>().map_err(|_| invalid())?;
+    let datetime = DateTime {
+        year: YearMapping::default()
+            .two_digit_year(year)
+            .ok_or_else(invalid)?,
+        month: number(date[1])?,
+        weekday: 0,
+        day: number(date[2])?,
+        hours: number(time[0])?,
+        minutes: number(time[1])?,
+        seconds: number(time[2])?,
+    };
+    // round trip to validate the date and compute the weekday
+    let timestamp = datetime.to_unix_timestamp().map_err(|_| invalid())?;
+    DateTime::from_unix_timestamp(timestamp).map_err(|_| invalid())
+}
+
+fn set_system_clock(timestamp: i64) -> Result<(), String> {
+    let time = libc::timespec {
+        tv_sec: timestamp as libc::time_t,
+        tv_nsec: 0,
+    };
+    // SAFETY: `time` is a valid timespec for the duration of the call.
+    if unsafe { libc::clock_settime(libc::CLOCK_REALTIME, &time) } != 0 {
+        return Err(format!(
+            "can't set the system clock: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,83 @@
+//! Calendar calculations used by the conversion and scheduling functions.
+//!
+//! Days are counted from the Unix epoch (1970-01-01), following the proleptic Gregorian calendar.
+
+/// Number of seconds in a day.
+pub(crate) const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Check if the year is a leap year.
+pub(crate) fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// Number of days in the month [1-12] of the year.
+pub(crate) fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Number of days from 1970-01-01 to the date.
+pub(crate) fn days_from_civil(year: i32, month: u8, day: u8) -> i64 {
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Date (year, month, day) from the number of days since 1970-01-01.
+pub(crate) fn civil_from_days(days: i64) -> (i32, u8, u8) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = (year_of_era + era * 400 + i64::from(month <= 2)) as i32;
+    (year, month, day)
+}
+
+/// Weekday [0-6] (0: Sunday) from the number of days since 1970-01-01 (a Thursday).
+pub(crate) fn weekday_from_days(days: i64) -> u8 {
+    (days + 4).rem_euclid(7) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_detect_leap_years() {
+        assert!(is_leap_year(2000));
+        assert!(is_leap_year(2024));
+        assert!(!is_leap_year(2021));
+        assert!(!is_leap_year(2100));
+    }
+
+    #[test]
+    fn can_count_days() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 1, 1), 10_957);
+        assert_eq!(days_from_civil(2021, 4, 4), 18_721);
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(10_957), (2000, 1, 1));
+        assert_eq!(civil_from_days(18_721), (2021, 4, 4));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+    }
+
+    #[test]
+    fn can_find_weekday() {
+        assert_eq!(weekday_from_days(0), 4); // Thursday
+        assert_eq!(weekday_from_days(10_957), 6); // Saturday
+        assert_eq!(weekday_from_days(18_721), 0); // Sunday
+    }
+}
@@ -0,0 +1,135 @@
+//! Measurement of the RTC crystal error using the clock output.
+//!
+//! The clock output is set to a low frequency (1 Hz or 32 Hz) and its edges are captured
+//! by a timer of the microcontroller, e.g. with an input capture channel. The timestamps
+//! or counts are fed to `CrystalCalibrator`, which compares the number of edges with the
+//! time elapsed on the reference clock. The longer the measurement, the more accurate
+//! the result: at 1 Hz, 1 ppm takes at least a million reference ticks per edge.
+
+use super::{hal, ClkoutFreq, Control, Error, OutOfRangeError, PCF8563};
+use hal::blocking::i2c::{Write, WriteRead};
+
+/// Crystal error computed from the clock output edges captured by a reference clock.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CrystalCalibrator {
+    frequency: ClkoutFreq,
+    reference_hz: u32,
+    last: Option<u32>,
+    edges: u64,
+    elapsed: u64,
+}
+
+impl CrystalCalibrator {
+    /// Create a new calibrator for the clock output frequency, captured by a reference
+    /// clock running at `reference_hz`.
+    ///
+    /// Will return an `OutOfRangeError` if the reference frequency is zero.
+    pub fn new(frequency: ClkoutFreq, reference_hz: u32) -> Result<Self, OutOfRangeError> {
+        if reference_hz == 0 {
+            return Err(OutOfRangeError);
+        }
+        Ok(CrystalCalibrator {
+            frequency,
+            reference_hz,
+            last: None,
+            edges: 0,
+            elapsed: 0,
+        })
+    }
+
+    /// Set the clock output frequency and enable it, with a single write.
+    pub fn start<I2C, E>(&self, rtc: &mut PCF8563<I2C>) -> Result<(), Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        rtc.set_clkout(self.frequency, Control::On)
+    }
+
+    /// Disable the clock output.
+    pub fn stop<I2C, E>(&self, rtc: &mut PCF8563<I2C>) -> Result<(), Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        rtc.control_clkout(Control::Off)
+    }
+
+    /// Record the timestamp of an edge, read from a free-running (wrapping) counter
+    /// of the reference clock.
+    ///
+    /// The edges must be consecutive, or less than one counter period apart.
+    pub fn capture(&mut self, timestamp: u32) {
+        if let Some(last) = self.last {
+            self.add_count(1, timestamp.wrapping_sub(last));
+        }
+        self.last = Some(timestamp);
+    }
+
+    /// Record a number of edges counted during a number of reference clock ticks.
+    pub fn add_count(&mut self, edges: u32, ticks: u32) {
+        self.edges += u64::from(edges);
+        self.elapsed += u64::from(ticks);
+    }
+
+    /// Number of edge periods recorded.
+    pub fn edges(&self) -> u64 {
+        self.edges
+    }
+
+    /// Crystal error in ppm rounded to the nearest, positive if the RTC is fast,
+    /// or `None` if nothing was recorded.
+    pub fn ppm_error(&self) -> Option<i32> {
+        if self.elapsed == 0 {
+            return None;
+        }
+        // edges * reference_hz / (frequency * elapsed) - 1
+        let expected = i128::from(self.frequency.hz()) * i128::from(self.elapsed);
+        let measured = i128::from(self.edges) * i128::from(self.reference_hz);
+        let error = (measured - expected) * 1_000_000;
+        let half = (expected / 2) * error.signum();
+        Some(((error + half) / expected) as i32)
+    }
+
+    /// Discard the recorded edges, to start a new measurement.
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.edges = 0;
+        self.elapsed = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cannot_create_calibrator_without_reference() {
+        assert_eq!(
+            CrystalCalibrator::new(ClkoutFreq::Clkout_1Hz, 0),
+            Err(OutOfRangeError)
+        );
+    }
+
+    #[test]
+    fn can_compute_error_from_timestamps() {
+        let mut calibrator = CrystalCalibrator::new(ClkoutFreq::Clkout_1Hz, 1_000_000).unwrap();
+        assert_eq!(calibrator.ppm_error(), None);
+        // 20 ppm fast, across the counter wrapping
+        let mut timestamp = u32::MAX - 1_000_000;
+        for _ in 0..11 {
+            calibrator.capture(timestamp);
+            timestamp = timestamp.wrapping_add(999_980);
+        }
+        assert_eq!(calibrator.edges(), 10);
+        assert_eq!(calibrator.ppm_error(), Some(20));
+        calibrator.reset();
+        assert_eq!(calibrator.ppm_error(), None);
+    }
+
+    #[test]
+    fn can_compute_error_from_counts() {
+        let mut calibrator = CrystalCalibrator::new(ClkoutFreq::Clkout_32Hz, 1_000_000).unwrap();
+        // 320 edges in 10.0005 s, 50 ppm slow
+        calibrator.add_count(320, 10_000_500);
+        assert_eq!(calibrator.ppm_error(), Some(-50));
+    }
+}
@@ -0,0 +1,81 @@
+//! Conversions from `chrono` types to the alarm types (requires the `chrono` feature).
+//!
+//! The seconds of a `NaiveTime` are ignored, as the alarm has a resolution of one minute.
+
+use super::{AlarmConfig, Weekday};
+use chrono::{NaiveTime, Timelike};
+
+impl From<chrono::Weekday> for Weekday {
+    fn from(weekday: chrono::Weekday) -> Self {
+        match weekday {
+            chrono::Weekday::Sun => Weekday::Sunday,
+            chrono::Weekday::Mon => Weekday::Monday,
+            chrono::Weekday::Tue => Weekday::Tuesday,
+            chrono::Weekday::Wed => Weekday::Wednesday,
+            chrono::Weekday::Thu => Weekday::Thursday,
+            chrono::Weekday::Fri => Weekday::Friday,
+            chrono::Weekday::Sat => Weekday::Saturday,
+        }
+    }
+}
+
+impl From<Weekday> for chrono::Weekday {
+    fn from(weekday: Weekday) -> Self {
+        match weekday {
+            Weekday::Sunday => chrono::Weekday::Sun,
+            Weekday::Monday => chrono::Weekday::Mon,
+            Weekday::Tuesday => chrono::Weekday::Tue,
+            Weekday::Wednesday => chrono::Weekday::Wed,
+            Weekday::Thursday => chrono::Weekday::Thu,
+            Weekday::Friday => chrono::Weekday::Fri,
+            Weekday::Saturday => chrono::Weekday::Sat,
+        }
+    }
+}
+
+/// Daily alarm at the time.
+impl From<NaiveTime> for AlarmConfig {
+    fn from(time: NaiveTime) -> Self {
+        AlarmConfig {
+            minutes: Some(time.minute() as u8),
+            hours: Some(time.hour() as u8),
+            day: None,
+            weekday: None,
+        }
+    }
+}
+
+/// Weekly alarm at the time on the weekday.
+impl From<(NaiveTime, chrono::Weekday)> for AlarmConfig {
+    fn from((time, weekday): (NaiveTime, chrono::Weekday)) -> Self {
+        AlarmConfig {
+            weekday: Some(Weekday::from(weekday).into()),
+            ..AlarmConfig::from(time)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_convert_time_and_weekday_to_alarm() {
+        let time = NaiveTime::from_hms_opt(7, 30, 15).unwrap();
+        assert_eq!(
+            AlarmConfig::from((time, chrono::Weekday::Sun)),
+            AlarmConfig {
+                minutes: Some(30),
+                hours: Some(7),
+                day: None,
+                weekday: Some(0),
+            }
+        );
+    }
+
+    #[test]
+    fn can_convert_weekdays() {
+        assert_eq!(Weekday::from(chrono::Weekday::Sat), Weekday::Saturday);
+        assert_eq!(chrono::Weekday::from(Weekday::Monday), chrono::Weekday::Mon);
+    }
+}
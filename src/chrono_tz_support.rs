@@ -0,0 +1,123 @@
+//! Local time in a named time zone of the tz database (requires the `chrono-tz` feature,
+//! which enables `std` and `chrono`).
+//!
+//! The RTC keeps the UTC time, which is converted to and from the local time in the time zone,
+//! including the daylight saving time, e.g. to show the local wall-clock time to the users:
+//!
+//! ```rust,ignore
+//! let now = rtc.get_local_datetime(chrono_tz::Europe::Berlin)?;
+//! println!("{}", now.format("%Y-%m-%d %H:%M:%S %Z"));
+//! ```
+
+use super::{hal, DateTime, Error, OutOfRangeError, YearMapping, PCF8563};
+use chrono::{Datelike, NaiveDate, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use hal::blocking::i2c::{Write, WriteRead};
+
+impl DateTime {
+    /// Convert the UTC date and time to the local time in the time zone, mapping the year as given.
+    ///
+    /// Will return an `OutOfRangeError` if any of the components is out of range.
+    pub fn to_zoned(
+        &self,
+        tz: Tz,
+        mapping: YearMapping,
+    ) -> Result<chrono::DateTime<Tz>, OutOfRangeError> {
+        if !self.is_valid_with(mapping) {
+            return Err(OutOfRangeError);
+        }
+        let utc = NaiveDate::from_ymd_opt(
+            i32::from(mapping.full_year(self.year).ok_or(OutOfRangeError)?),
+            u32::from(self.month),
+            u32::from(self.day),
+        )
+        .and_then(|date| {
+            date.and_hms_opt(
+                u32::from(self.hours),
+                u32::from(self.minutes),
+                u32::from(self.seconds),
+            )
+        })
+        .ok_or(OutOfRangeError)?;
+        Ok(Utc.from_utc_datetime(&utc).with_timezone(&tz))
+    }
+
+    /// Create from a date and time in any time zone, converted to UTC and mapping the year as given.
+    ///
+    /// The weekday is set counting from Sunday (0), a leap second is kept at 59.
+    pub fn from_zoned<T: TimeZone>(
+        datetime: &chrono::DateTime<T>,
+        mapping: YearMapping,
+    ) -> Result<Self, OutOfRangeError> {
+        let utc = datetime.naive_utc();
+        Ok(DateTime {
+            year: mapping.two_digit_year(utc.year()).ok_or(OutOfRangeError)?,
+            month: utc.month() as u8,
+            weekday: utc.weekday().num_days_from_sunday() as u8,
+            day: utc.day() as u8,
+            hours: utc.hour() as u8,
+            minutes: utc.minute() as u8,
+            seconds: utc.second().min(59) as u8,
+        })
+    }
+}
+
+impl<I2C, E> PCF8563<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Read the date and time as the local time in the time zone, according to the year mapping.
+    pub fn get_local_datetime(&mut self, tz: Tz) -> Result<chrono::DateTime<Tz>, Error<E>> {
+        let mapping = self.year_mapping;
+        Ok(self.get_datetime()?.to_zoned(tz, mapping)?)
+    }
+
+    /// Set the date and time from a local time, stored as UTC according to the year mapping.
+    ///
+    /// Will return an 'Error::InvalidInputData' if the year can't be represented.
+    pub fn set_local_datetime<T: TimeZone>(
+        &mut self,
+        datetime: &chrono::DateTime<T>,
+    ) -> Result<(), Error<E>> {
+        let datetime = DateTime::from_zoned(datetime, self.year_mapping)?;
+        self.set_datetime(&datetime)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe::Berlin;
+
+    const SUMMER: DateTime = DateTime {
+        year: 21,
+        month: 7,
+        weekday: 0,
+        day: 4,
+        hours: 10,
+        minutes: 30,
+        seconds: 0,
+    };
+
+    #[test]
+    fn converts_to_local_time_with_dst() {
+        let local = SUMMER.to_zoned(Berlin, YearMapping::default()).unwrap();
+        assert_eq!(local.hour(), 12);
+        let winter = DateTime {
+            month: 1,
+            day: 3,
+            ..SUMMER
+        };
+        let local = winter.to_zoned(Berlin, YearMapping::default()).unwrap();
+        assert_eq!(local.hour(), 11);
+    }
+
+    #[test]
+    fn converts_from_local_time() {
+        let local = Berlin.with_ymd_and_hms(2021, 7, 4, 12, 30, 0).unwrap();
+        assert_eq!(
+            DateTime::from_zoned(&local, YearMapping::default()),
+            Ok(SUMMER)
+        );
+    }
+}
@@ -23,6 +23,104 @@ impl ClkoutFreq {
     pub fn bits(self) -> u8 {
         self as u8
     }
+
+    /// Frequency in Hz.
+    pub(crate) const fn hz(self) -> u32 {
+        match self {
+            ClkoutFreq::Clkout_32768Hz => 32_768,
+            ClkoutFreq::Clkout_1024Hz => 1_024,
+            ClkoutFreq::Clkout_32Hz => 32,
+            ClkoutFreq::Clkout_1Hz => 1,
+        }
+    }
+
+    /// Frequency with the number of Hz, if any.
+    pub(crate) const fn from_hz(hz: u32) -> Option<Self> {
+        match hz {
+            32_768 => Some(ClkoutFreq::Clkout_32768Hz),
+            1_024 => Some(ClkoutFreq::Clkout_1024Hz),
+            32 => Some(ClkoutFreq::Clkout_32Hz),
+            1 => Some(ClkoutFreq::Clkout_1Hz),
+            _ => None,
+        }
+    }
+
+    /// Decode the frequency from the clock output control register.
+    pub(crate) fn from_bits(data: u8) -> Self {
+        match data & 0b0000_0011 {
+            0b00 => ClkoutFreq::Clkout_32768Hz,
+            0b01 => ClkoutFreq::Clkout_1024Hz,
+            0b10 => ClkoutFreq::Clkout_32Hz,
+            _ => ClkoutFreq::Clkout_1Hz,
+        }
+    }
+}
+
+/// Clock output configuration, read and written with `get_clkout_config()`/`set_clkout_config()`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ClkoutConfig {
+    /// Clock output enabled.
+    pub enabled: bool,
+    /// Clock output frequency.
+    pub frequency: ClkoutFreq,
+}
+
+/// Token proving that the clock output is enabled at `HZ` [1, 32, 1024 or 32768],
+/// returned by `PCF8563::enable_clkout()`.
+///
+/// Code depending on the clock output, e.g. a charge pump driver, can require the token.
+/// It can't be copied and is consumed by `PCF8563::disable_clkout()`.
+#[derive(Debug)]
+pub struct ClkoutActive<const HZ: u32> {
+    _private: (),
+}
+
+impl<const HZ: u32> ClkoutActive<HZ> {
+    /// Frequency of the clock output.
+    pub fn frequency(&self) -> ClkoutFreq {
+        // checked when the token was created
+        ClkoutFreq::from_hz(HZ).unwrap_or(ClkoutFreq::Clkout_32768Hz)
+    }
+}
+
+/// Clock output enabled for a limited scope, created by `PCF8563::enable_clkout_scoped()`.
+///
+/// The clock output is disabled when the guard is dropped, ignoring any error,
+/// or by `disable()` which returns it.
+#[derive(Debug)]
+pub struct ClkoutGuard<'a, I2C>
+where
+    I2C: Write + WriteRead<Error = <I2C as Write>::Error>,
+{
+    rtc: &'a mut PCF8563<I2C>,
+    active: bool,
+}
+
+impl<'a, I2C, E> ClkoutGuard<'a, I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Access the driver while the clock output is enabled.
+    pub fn rtc(&mut self) -> &mut PCF8563<I2C> {
+        self.rtc
+    }
+
+    /// Disable the clock output now, returning the error if any.
+    pub fn disable(mut self) -> Result<(), Error<E>> {
+        self.active = false;
+        self.rtc.control_clkout(Control::Off)
+    }
+}
+
+impl<'a, I2C> Drop for ClkoutGuard<'a, I2C>
+where
+    I2C: Write + WriteRead<Error = <I2C as Write>::Error>,
+{
+    fn drop(&mut self) {
+        if self.active {
+            let _ = self.rtc.control_clkout(Control::Off);
+        }
+    }
 }
 
 impl<I2C, E> PCF8563<I2C>
@@ -37,6 +135,65 @@ where
         self.write_register(Register::CLKOUT_CTRL, data)
     }
 
+    /// Set the clock output frequency and enable or disable it, with a single write.
+    pub fn set_clkout(&mut self, frequency: ClkoutFreq, status: Control) -> Result<(), Error<E>> {
+        let data = match status {
+            Control::On => BitFlags::FE | frequency.bits(),
+            Control::Off => frequency.bits(),
+        };
+        self.write_register(Register::CLKOUT_CTRL, data)
+    }
+
+    /// Write the whole clock output configuration with a single write.
+    pub fn set_clkout_config(&mut self, config: &ClkoutConfig) -> Result<(), Error<E>> {
+        let status = if config.enabled {
+            Control::On
+        } else {
+            Control::Off
+        };
+        self.set_clkout(config.frequency, status)
+    }
+
+    /// Read the whole clock output configuration in one transaction.
+    pub fn get_clkout_config(&mut self) -> Result<ClkoutConfig, Error<E>> {
+        let data = self.read_register(Register::CLKOUT_CTRL)?;
+        Ok(ClkoutConfig {
+            enabled: data & BitFlags::FE != 0,
+            frequency: ClkoutFreq::from_bits(data),
+        })
+    }
+
+    /// Set the clock output frequency and enable it with a single write, returning
+    /// a guard which disables it again when dropped.
+    pub fn enable_clkout_scoped(
+        &mut self,
+        frequency: ClkoutFreq,
+    ) -> Result<ClkoutGuard<'_, I2C>, Error<E>> {
+        self.set_clkout(frequency, Control::On)?;
+        Ok(ClkoutGuard {
+            rtc: self,
+            active: true,
+        })
+    }
+
+    /// Set the clock output frequency to `HZ` and enable it with a single write,
+    /// returning a token proving it.
+    ///
+    /// Will return an 'Error::InvalidInputData' if `HZ` is not a clock output frequency.
+    pub fn enable_clkout<const HZ: u32>(&mut self) -> Result<ClkoutActive<HZ>, Error<E>> {
+        let frequency = ClkoutFreq::from_hz(HZ).ok_or(Error::InvalidInputData)?;
+        self.set_clkout(frequency, Control::On)?;
+        Ok(ClkoutActive { _private: () })
+    }
+
+    /// Disable the clock output enabled by `enable_clkout()`, consuming the token.
+    pub fn disable_clkout<const HZ: u32>(
+        &mut self,
+        _active: ClkoutActive<HZ>,
+    ) -> Result<(), Error<E>> {
+        self.control_clkout(Control::Off)
+    }
+
     /// Enable or disable clock output.
     pub fn control_clkout(&mut self, status: Control) -> Result<(), Error<E>> {
         match status {
@@ -50,7 +207,64 @@ where
         self.is_register_bit_flag_high(Register::CLKOUT_CTRL, BitFlags::FE)
     }
 
-    //TO DO:
-    //
-    // pub fn get_clkout_frequency()
+    /// Read the clock output frequency.
+    pub fn get_clkout_frequency(&mut self) -> Result<ClkoutFreq, Error<E>> {
+        Ok(self.get_clkout_config()?.frequency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::I2cMock;
+
+    #[test]
+    fn can_set_clkout() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        rtc.set_clkout(ClkoutFreq::Clkout_1Hz, Control::On).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CLKOUT_CTRL, 0x83]);
+    }
+
+    #[test]
+    fn can_set_and_get_clkout_config() {
+        let config = ClkoutConfig {
+            enabled: true,
+            frequency: ClkoutFreq::Clkout_32Hz,
+        };
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x82]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.set_clkout_config(&config).unwrap();
+        assert_eq!(rtc.get_clkout_config().unwrap(), config);
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CLKOUT_CTRL]);
+    }
+
+    #[test]
+    fn scoped_clkout_is_disabled_on_drop() {
+        let mut i2c = I2cMock::new();
+        // read when checking, then when disabling
+        i2c.set_read_data(&[0x80, 0x80]);
+        let mut rtc = PCF8563::new(i2c);
+        {
+            let mut guard = rtc
+                .enable_clkout_scoped(ClkoutFreq::Clkout_32768Hz)
+                .unwrap();
+            assert!(guard.rtc().is_clkout_enabled().unwrap());
+        }
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CLKOUT_CTRL, 0x00]);
+    }
+
+    #[test]
+    fn can_enable_clkout_with_token() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        let active = rtc.enable_clkout::<1024>().unwrap();
+        assert_eq!(active.frequency(), ClkoutFreq::Clkout_1024Hz);
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CLKOUT_CTRL, 0x81]);
+        let mut rtc = PCF8563::new(i2c);
+        assert!(rtc.enable_clkout::<100>().is_err());
+    }
 }
@@ -0,0 +1,374 @@
+//! Text commands to read and set the RTC over a serial port (requires the `commands` feature).
+//!
+//! One command per line, terminated by `\n` (a preceding `\r` is ignored):
+//!
+//! | Command                     | Response                    |
+//! |-----------------------------|-----------------------------|
+//! | `TIME?`                     | `TIME=2025-06-04T12:00:00`  |
+//! | `TIME=2025-06-04T12:00:00`  | `OK`                        |
+//! | `ALARM?`                    | `ALARM=06:30` or `ALARM=OFF`|
+//! | `ALARM=06:30`               | `OK`                        |
+//! | `ALARM=OFF`                 | `OK`                        |
+//!
+//! Invalid commands, and commands which fail, are answered with `ERR`.
+//! The time is set and reported according to the year mapping of the driver.
+//!
+//! ```rust,ignore
+//! let mut buffer = [0; 32];
+//! loop {
+//!     rtc.serve_command(&mut uart, &mut buffer)?;
+//! }
+//! ```
+
+use super::{hal, AlarmConfig, Control, DateTime, Error, YearMapping, PCF8563};
+use embedded_io::{Read, Write};
+use hal::blocking::i2c::{Write as I2cWrite, WriteRead};
+
+/// Error returned when a command line can't be parsed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CommandParseError;
+
+/// Error while serving a command.
+#[derive(Debug)]
+pub enum CommandError<E, IoE> {
+    /// RTC error, `ERR` was sent in response
+    Rtc(Error<E>),
+    /// Serial port error
+    Io(IoE),
+    /// The serial port was closed before the end of the line
+    UnexpectedEof,
+}
+
+/// Command received over the serial port.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    /// `TIME?`: read the date and time
+    GetTime,
+    /// `TIME=YYYY-MM-DDTHH:MM:SS`: set the date and time
+    SetTime(DateTime),
+    /// `ALARM?`: read the daily alarm
+    GetAlarm,
+    /// `ALARM=HH:MM`: set a daily alarm and enable the alarm interrupt
+    SetAlarm {
+        /// Hours [0-23]
+        hours: u8,
+        /// Minutes [0-59]
+        minutes: u8,
+    },
+    /// `ALARM=OFF`: disable the alarm and the alarm interrupt
+    DisableAlarm,
+}
+
+impl Command {
+    /// Parse a command line, the year is taken as 2000-2099.
+    pub fn parse(line: &[u8]) -> Result<Self, CommandParseError> {
+        Command::parse_with(line, YearMapping::default())
+    }
+
+    /// Parse a command line, mapping the year as given.
+    pub fn parse_with(line: &[u8], mapping: YearMapping) -> Result<Self, CommandParseError> {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        match line {
+            b"TIME?" => Ok(Command::GetTime),
+            b"ALARM?" => Ok(Command::GetAlarm),
+            b"ALARM=OFF" => Ok(Command::DisableAlarm),
+            [b'T', b'I', b'M', b'E', b'=', value @ ..] => {
+                parse_datetime(value, mapping).map(Command::SetTime)
+            }
+            [b'A', b'L', b'A', b'R', b'M', b'=', h1, h0, b':', m1, m0] => {
+                let hours = parse_number(&[*h1, *h0])?;
+                let minutes = parse_number(&[*m1, *m0])?;
+                if hours > 23 || minutes > 59 {
+                    return Err(CommandParseError);
+                }
+                Ok(Command::SetAlarm {
+                    hours: hours as u8,
+                    minutes: minutes as u8,
+                })
+            }
+            _ => Err(CommandParseError),
+        }
+    }
+}
+
+impl<I2C, E> PCF8563<I2C>
+where
+    I2C: I2cWrite<Error = E> + WriteRead<Error = E>,
+{
+    /// Read one command line from the serial port, execute it and send the response.
+    ///
+    /// The buffer must hold the longest command (26 bytes), longer lines are answered with `ERR`.
+    pub fn serve_command<IO: Read + Write>(
+        &mut self,
+        io: &mut IO,
+        buffer: &mut [u8],
+    ) -> Result<(), CommandError<E, IO::Error>> {
+        let mut len = 0;
+        let mut overflow = false;
+        loop {
+            let mut byte = [0];
+            if io.read(&mut byte).map_err(CommandError::Io)? == 0 {
+                return Err(CommandError::UnexpectedEof);
+            }
+            if byte[0] == b'\n' {
+                break;
+            }
+            match buffer.get_mut(len) {
+                Some(slot) => {
+                    *slot = byte[0];
+                    len += 1;
+                }
+                None => overflow = true,
+            }
+        }
+        let command = if overflow {
+            Err(CommandParseError)
+        } else {
+            Command::parse_with(&buffer[..len], self.year_mapping)
+        };
+        match command {
+            Ok(command) => self.execute_command(&command, io),
+            Err(CommandParseError) => io.write_all(b"ERR\r\n").map_err(CommandError::Io),
+        }
+    }
+
+    /// Execute a command and send the response.
+    pub fn execute_command<W: Write>(
+        &mut self,
+        command: &Command,
+        out: &mut W,
+    ) -> Result<(), CommandError<E, W::Error>> {
+        let mut response = [0; 32];
+        let result = match *command {
+            Command::GetTime => self.get_datetime().and_then(|datetime| {
+                let year = self
+                    .year_mapping
+                    .full_year(datetime.year)
+                    .ok_or(Error::InvalidInputData)?;
+                let mut text = Text::new(&mut response, b"TIME=");
+                text.number(year, 4);
+                text.separated(b'-', datetime.month);
+                text.separated(b'-', datetime.day);
+                text.separated(b'T', datetime.hours);
+                text.separated(b':', datetime.minutes);
+                text.separated(b':', datetime.seconds);
+                Ok(text.len)
+            }),
+            Command::SetTime(datetime) => self
+                .set_datetime(&datetime)
+                .map(|_| Text::new(&mut response, b"OK").len),
+            Command::GetAlarm => self.get_alarm().map(|alarm| match alarm {
+                AlarmConfig {
+                    minutes: Some(minutes),
+                    hours: Some(hours),
+                    day: None,
+                    weekday: None,
+                } => {
+                    let mut text = Text::new(&mut response, b"ALARM=");
+                    text.number(u16::from(hours), 2);
+                    text.separated(b':', minutes);
+                    text.len
+                }
+                _ => Text::new(&mut response, b"ALARM=OFF").len,
+            }),
+            Command::SetAlarm { hours, minutes } => self
+                .clear_alarm_flag()
+                .and_then(|_| self.set_alarm_time(hours, minutes))
+                .and_then(|_| self.control_alarm_interrupt(Control::On))
+                .map(|_| Text::new(&mut response, b"OK").len),
+            Command::DisableAlarm => self
+                .disable_all_alarms()
+                .and_then(|_| self.control_alarm_interrupt(Control::Off))
+                .map(|_| Text::new(&mut response, b"OK").len),
+        };
+        match result {
+            Ok(len) => {
+                out.write_all(&response[..len]).map_err(CommandError::Io)?;
+                out.write_all(b"\r\n").map_err(CommandError::Io)
+            }
+            Err(error) => {
+                out.write_all(b"ERR\r\n").map_err(CommandError::Io)?;
+                Err(CommandError::Rtc(error))
+            }
+        }
+    }
+}
+
+/// Response being written into a buffer.
+struct Text<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Text<'a> {
+    fn new(buffer: &'a mut [u8], prefix: &[u8]) -> Self {
+        buffer[..prefix.len()].copy_from_slice(prefix);
+        Text {
+            buffer,
+            len: prefix.len(),
+        }
+    }
+
+    fn number(&mut self, value: u16, digits: usize) {
+        let mut value = value;
+        for i in (0..digits).rev() {
+            self.buffer[self.len + i] = b'0' + (value % 10) as u8;
+            value /= 10;
+        }
+        self.len += digits;
+    }
+
+    fn separated(&mut self, separator: u8, value: u8) {
+        self.buffer[self.len] = separator;
+        self.len += 1;
+        self.number(u16::from(value), 2);
+    }
+}
+
+/// Parse `YYYY-MM-DDTHH:MM:SS`, a trailing `Z` is accepted.
+fn parse_datetime(value: &[u8], mapping: YearMapping) -> Result<DateTime, CommandParseError> {
+    let value = value.strip_suffix(b"Z").unwrap_or(value);
+    if value.len() != 19
+        || value[4] != b'-'
+        || value[7] != b'-'
+        || value[10] != b'T'
+        || value[13] != b':'
+        || value[16] != b':'
+    {
+        return Err(CommandParseError);
+    }
+    let year = parse_number(&value[0..4])?;
+    let datetime = DateTime {
+        year: mapping
+            .two_digit_year(i32::from(year))
+            .ok_or(CommandParseError)?,
+        month: parse_number(&value[5..7])? as u8,
+        weekday: 0,
+        day: parse_number(&value[8..10])? as u8,
+        hours: parse_number(&value[11..13])? as u8,
+        minutes: parse_number(&value[14..16])? as u8,
+        seconds: parse_number(&value[17..19])? as u8,
+    };
+    // round trip to validate the date and compute the weekday
+    let timestamp = datetime
+        .to_unix_timestamp_with(mapping)
+        .map_err(|_| CommandParseError)?;
+    DateTime::from_unix_timestamp_with(timestamp, mapping).map_err(|_| CommandParseError)
+}
+
+fn parse_number(digits: &[u8]) -> Result<u16, CommandParseError> {
+    digits.iter().try_fold(0, |value, &digit| match digit {
+        b'0'..=b'9' => Ok(value * 10 + u16::from(digit - b'0')),
+        _ => Err(CommandParseError),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+    use embedded_hal_mock::I2cMock;
+
+    /// Serial port replaying the input and keeping the output.
+    struct Port {
+        input: &'static [u8],
+        output: [u8; 64],
+        len: usize,
+    }
+
+    impl Port {
+        fn new(input: &'static [u8]) -> Self {
+            Port {
+                input,
+                output: [0; 64],
+                len: 0,
+            }
+        }
+
+        fn output(&self) -> &[u8] {
+            &self.output[..self.len]
+        }
+    }
+
+    impl embedded_io::ErrorType for Port {
+        type Error = Infallible;
+    }
+
+    impl Read for Port {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for Port {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.output[self.len..self.len + buf.len()].copy_from_slice(buf);
+            self.len += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn can_parse_commands() {
+        assert_eq!(Command::parse(b"TIME?\r"), Ok(Command::GetTime));
+        assert_eq!(
+            Command::parse(b"TIME=2021-04-04T07:15:42"),
+            Ok(Command::SetTime(DateTime {
+                year: 21,
+                month: 4,
+                weekday: 0,
+                day: 4,
+                hours: 7,
+                minutes: 15,
+                seconds: 42,
+            }))
+        );
+        assert_eq!(
+            Command::parse(b"ALARM=06:30"),
+            Ok(Command::SetAlarm {
+                hours: 6,
+                minutes: 30
+            })
+        );
+        assert_eq!(Command::parse(b"ALARM=OFF"), Ok(Command::DisableAlarm));
+        for line in [
+            &b"TIME"[..],
+            b"TIME=2021-02-29T07:15:42",
+            b"TIME=1999-04-04T07:15:42",
+            b"ALARM=24:00",
+            b"ALARM=6:30",
+        ] {
+            assert_eq!(Command::parse(line), Err(CommandParseError));
+        }
+    }
+
+    #[test]
+    fn can_serve_time_query() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x42, 0x15, 0x07, 0x04, 0x00, 0x04, 0x21]);
+        let mut rtc = PCF8563::new(i2c);
+        let mut port = Port::new(b"TIME?\r\n");
+        let mut buffer = [0; 32];
+        rtc.serve_command(&mut port, &mut buffer).unwrap();
+        assert_eq!(port.output(), b"TIME=2021-04-04T07:15:42\r\n");
+    }
+
+    #[test]
+    fn answers_invalid_commands_with_error() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        let mut port = Port::new(b"TIME=2021-04-04T07:15:42\nTIME?");
+        let mut buffer = [0; 8];
+        rtc.serve_command(&mut port, &mut buffer).unwrap();
+        assert_eq!(port.output(), b"ERR\r\n");
+        assert!(matches!(
+            rtc.serve_command(&mut port, &mut buffer),
+            Err(CommandError::UnexpectedEof)
+        ));
+        let i2c = rtc.destroy();
+        assert!(i2c.get_write_data().is_empty());
+    }
+}
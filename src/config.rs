@@ -0,0 +1,115 @@
+//! Configuration of the clock output, timer and interrupts as a whole.
+
+use super::{
+    hal, BitFlags, ClkoutConfig, ClkoutFreq, Error, Register, TimerConfig, TimerFreq, TimerMode,
+    PCF8563,
+};
+use hal::blocking::i2c::{Write, WriteRead};
+
+/// Configuration of the clock output, timer and interrupts, read and written with
+/// `get_config()`/`set_config()`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// Clock output configuration.
+    pub clkout: ClkoutConfig,
+    /// Timer configuration, including the timer interrupt.
+    pub timer: TimerConfig,
+    /// Alarm interrupt enabled.
+    pub alarm_interrupt: bool,
+}
+
+impl<I2C, E> PCF8563<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Write the whole configuration in two transactions: the control register
+    /// then the clock output control, timer control and timer registers.
+    ///
+    /// Both flags are kept. The timer mode follows the output mode.
+    pub fn set_config(&mut self, config: &Config) -> Result<(), Error<E>> {
+        let mut data = BitFlags::AF | BitFlags::TF;
+        if config.alarm_interrupt {
+            data |= BitFlags::AIE;
+        }
+        if config.timer.interrupt {
+            data |= BitFlags::TIE;
+        }
+        if config.timer.pulse_mode {
+            data |= BitFlags::TI_TP;
+        }
+        self.write_register(Register::CTRL_STATUS_2, data)?;
+        let mut clkout = config.clkout.frequency.bits();
+        if config.clkout.enabled {
+            clkout |= BitFlags::FE;
+        }
+        let mut timer = config.timer.frequency.bits();
+        if config.timer.enabled {
+            timer |= BitFlags::TE;
+        }
+        self.write_registers(Register::CLKOUT_CTRL, &[clkout, timer, config.timer.ticks])?;
+        self.timer_mode = if config.timer.pulse_mode {
+            TimerMode::Periodic
+        } else {
+            TimerMode::OneShot
+        };
+        Ok(())
+    }
+
+    /// Read the whole configuration in two transactions.
+    pub fn get_config(&mut self) -> Result<Config, Error<E>> {
+        let status = self.read_register(Register::CTRL_STATUS_2)?;
+        let mut data = [0; 3];
+        self.read_registers(Register::CLKOUT_CTRL, &mut data)?;
+        Ok(Config {
+            clkout: ClkoutConfig {
+                enabled: data[0] & BitFlags::FE != 0,
+                frequency: ClkoutFreq::from_bits(data[0]),
+            },
+            timer: TimerConfig {
+                enabled: data[1] & BitFlags::TE != 0,
+                frequency: TimerFreq::from_bits(data[1]),
+                ticks: data[2],
+                interrupt: status & BitFlags::TIE != 0,
+                pulse_mode: status & BitFlags::TI_TP != 0,
+            },
+            alarm_interrupt: status & BitFlags::AIE != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::I2cMock;
+
+    #[test]
+    fn can_set_and_get_config() {
+        let config = Config {
+            clkout: ClkoutConfig {
+                enabled: false,
+                frequency: ClkoutFreq::Clkout_1Hz,
+            },
+            timer: TimerConfig {
+                enabled: true,
+                frequency: TimerFreq::Timer_1Hz,
+                ticks: 10,
+                interrupt: true,
+                pulse_mode: false,
+            },
+            alarm_interrupt: true,
+        };
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x03, 0x03, 0x82, 0x0a]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.set_config(&config).unwrap();
+        assert_eq!(rtc.get_timer_mode(), TimerMode::OneShot);
+        assert_eq!(rtc.get_config().unwrap(), config);
+        let mut rtc = PCF8563::new(rtc.destroy());
+        rtc.set_config(&config).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(
+            i2c.get_write_data(),
+            &[Register::CLKOUT_CTRL, 0x03, 0x82, 10]
+        );
+    }
+}
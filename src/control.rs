@@ -1,14 +1,86 @@
 //! Various functions related to the RTC control that are not specifically
 //! datetime-, timer-, alarm- or clock output-related will be defined here
 
-use super::{hal, BitFlags, Control, Error, Register, TimerFreq, PCF8563};
+#[cfg(feature = "danger")]
+use super::DateTime;
+use super::{
+    hal, BitFlags, ClkoutFreq, Control, Error, InterruptOutput, Register, TimerFreq, TimerMode,
+    PCF8563,
+};
 use hal::blocking::i2c::{Write, WriteRead};
 
+/// Source of an interrupt, as reported by `handle_interrupt()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InterruptSource {
+    /// The alarm flag was set.
+    Alarm,
+    /// The timer flag was set.
+    Timer,
+    /// Both the alarm and the timer flags were set.
+    Both,
+    /// No flag was set.
+    None,
+}
+
+/// Interrupt configuration, read and written with `get_interrupt_config()`/`set_interrupt_config()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterruptConfig {
+    /// Alarm interrupt (AIE) enabled.
+    pub alarm: bool,
+    /// Timer interrupt (TIE) enabled.
+    pub timer: bool,
+    /// Timer interrupt output mode (TI_TP).
+    pub timer_output: InterruptOutput,
+}
+
+/// Bits of both control registers, read with `status()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Status {
+    /// Is the clock stopped (STOP)?
+    pub stopped: bool,
+    /// Is the external clock test mode (TEST1) enabled?
+    pub ext_clk_test_mode: bool,
+    /// Is the power-on reset override (TESTC) enabled?
+    pub power_on_reset_override: bool,
+    /// Is the timer interrupt output pulsating (TI_TP)?
+    pub pulse_mode: bool,
+    /// Is the alarm flag (AF) set?
+    pub alarm_flag: bool,
+    /// Is the timer flag (TF) set?
+    pub timer_flag: bool,
+    /// Is the alarm interrupt (AIE) enabled?
+    pub alarm_interrupt_enabled: bool,
+    /// Is the timer interrupt (TIE) enabled?
+    pub timer_interrupt_enabled: bool,
+}
+
+/// Handling of the clock output by `rtc_init_with()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClkoutInit {
+    /// Leave the clock output as it is (enabled at 32768 Hz after power-on).
+    #[default]
+    Leave,
+    /// Disable the clock output, e.g. when the pin is not used.
+    Disable,
+    /// Enable the clock output at the frequency.
+    Enable(ClkoutFreq),
+}
+
+/// Initialization options for `rtc_init_with()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InitConfig {
+    /// Handling of the clock output.
+    pub clkout: ClkoutInit,
+}
+
 impl<I2C, E> PCF8563<I2C>
 where
     I2C: Write<Error = E> + WriteRead<Error = E>,
 {
-    /// Enable or disable external clock test mode.
+    /// Enable or disable external clock test mode (requires the `danger` feature).
+    ///
+    /// In this mode the clock is driven from the CLKOUT pin, normal timekeeping stops.
+    #[cfg(feature = "danger")]
     pub fn control_ext_clk_test_mode(&mut self, flag: Control) -> Result<(), Error<E>> {
         match flag {
             Control::On => self.set_register_bit_flag(Register::CTRL_STATUS_1, BitFlags::TEST1),
@@ -16,6 +88,34 @@ where
         }
     }
 
+    /// Enter the external clock test mode with the date and time, for test fixtures driving
+    /// the prescaler from a reference source (requires the `danger` feature).
+    ///
+    /// Following the datasheet, TEST1 and STOP are set, STOP is cleared to reset the prescaler,
+    /// then the date and time are written, clearing the voltage low flag. The CLKOUT pin then
+    /// becomes an input and must be driven with a square wave between VSS and VDD: the seconds
+    /// increment after the first 32 positive edges, then after every 64 positive edges. Interrupts and alarms keep working on the counted time.
+    ///
+    /// Will return an 'Error::InvalidInputData' if any of the date and time components is out of range.
+    #[cfg(feature = "danger")]
+    pub fn enter_external_clock_mode(&mut self, datetime: &DateTime) -> Result<(), Error<E>> {
+        if !datetime.is_valid() {
+            return Err(Error::InvalidInputData);
+        }
+        self.write_register(Register::CTRL_STATUS_1, BitFlags::TEST1 | BitFlags::STOP)?;
+        self.write_register(Register::CTRL_STATUS_1, BitFlags::TEST1)?;
+        self.set_datetime(datetime)
+    }
+
+    /// Leave the external clock test mode, with the clock running from the crystal again
+    /// (requires the `danger` feature).
+    ///
+    /// The time counted from the external clock is kept, set it again if needed.
+    #[cfg(feature = "danger")]
+    pub fn exit_external_clock_mode(&mut self) -> Result<(), Error<E>> {
+        self.write_register(Register::CTRL_STATUS_1, 0)
+    }
+
     /// Is the external clock test mode enabled?
     pub fn is_ext_clk_mode_enabled(&mut self) -> Result<bool, Error<E>> {
         self.is_register_bit_flag_high(Register::CTRL_STATUS_1, BitFlags::TEST1)
@@ -36,7 +136,8 @@ where
         Ok(flag)
     }
 
-    /// Enable or disable power-on-reset override facility.
+    /// Enable or disable power-on-reset override facility (requires the `danger` feature).
+    #[cfg(feature = "danger")]
     pub fn control_power_on_reset_override(&mut self, flag: Control) -> Result<(), Error<E>> {
         match flag {
             Control::On => self.set_register_bit_flag(Register::CTRL_STATUS_1, BitFlags::TESTC),
@@ -49,6 +150,16 @@ where
         self.is_register_bit_flag_high(Register::CTRL_STATUS_1, BitFlags::TESTC)
     }
 
+    /// Disable the external clock test mode and the power-on-reset override facility
+    /// with a single write, keeping the clock running or stopped.
+    pub fn clear_test_modes(&mut self) -> Result<(), Error<E>> {
+        let data = self.read_register(Register::CTRL_STATUS_1)?;
+        self.write_register(
+            Register::CTRL_STATUS_1,
+            data & !(BitFlags::TEST1 | BitFlags::TESTC),
+        )
+    }
+
     /// Check the status of the Voltage Low detector flag
     pub fn get_voltage_low_flag(&mut self) -> Result<bool, Error<E>> {
         self.is_register_bit_flag_high(Register::VL_SECONDS, BitFlags::VL)
@@ -59,13 +170,346 @@ where
         self.clear_register_bit_flag(Register::VL_SECONDS, BitFlags::VL)
     }
 
+    /// Clear both the alarm and the timer flags with a single write, keeping the interrupt settings.
+    pub fn clear_all_interrupt_flags(&mut self) -> Result<(), Error<E>> {
+        let data = self.read_register(Register::CTRL_STATUS_2)?;
+        self.write_register(
+            Register::CTRL_STATUS_2,
+            data & (BitFlags::TI_TP | BitFlags::AIE | BitFlags::TIE),
+        )
+    }
+
+    /// Clear the alarm, timer and voltage low flags with two writes, e.g. after servicing
+    /// a wake-up or recovering from a brown-out, keeping the interrupt settings.
+    pub fn clear_all_flags(&mut self) -> Result<(), Error<E>> {
+        self.clear_all_interrupt_flags()?;
+        self.clear_voltage_low_flag()
+    }
+
+    /// Read the alarm and timer flags, and clear those which were set.
+    ///
+    /// Meant for the handler of the interrupt pin: at most one read and one write,
+    /// a flag set by the RTC in between is left set for the next call.
+    pub fn handle_interrupt(&mut self) -> Result<InterruptSource, Error<E>> {
+        let data = self.read_register(Register::CTRL_STATUS_2)?;
+        let flags = data & (BitFlags::AF | BitFlags::TF);
+        if flags == 0 {
+            return Ok(InterruptSource::None);
+        }
+        let settings = data & (BitFlags::TI_TP | BitFlags::AIE | BitFlags::TIE);
+        self.write_register(
+            Register::CTRL_STATUS_2,
+            settings | (!flags & (BitFlags::AF | BitFlags::TF)),
+        )?;
+        Ok(
+            match (flags & BitFlags::AF != 0, flags & BitFlags::TF != 0) {
+                (true, true) => InterruptSource::Both,
+                (true, false) => InterruptSource::Alarm,
+                _ => InterruptSource::Timer,
+            },
+        )
+    }
+
+    /// Same as `handle_interrupt()`, calling `on_alarm` if the alarm flag was set,
+    /// then `on_timer` if the timer flag was set.
+    ///
+    /// Meant for superloop firmware polling the RTC, or the handler of the interrupt pin.
+    pub fn service<A, T>(
+        &mut self,
+        mut on_alarm: A,
+        mut on_timer: T,
+    ) -> Result<InterruptSource, Error<E>>
+    where
+        A: FnMut(),
+        T: FnMut(),
+    {
+        let source = self.handle_interrupt()?;
+        if let InterruptSource::Alarm | InterruptSource::Both = source {
+            on_alarm();
+        }
+        if let InterruptSource::Timer | InterruptSource::Both = source {
+            on_timer();
+        }
+        Ok(source)
+    }
+
+    /// Write the interrupt configuration with a single write, keeping both flags.
+    /// The timer mode follows the output mode.
+    pub fn set_interrupt_config(&mut self, config: &InterruptConfig) -> Result<(), Error<E>> {
+        let mut data = BitFlags::AF | BitFlags::TF;
+        if config.alarm {
+            data |= BitFlags::AIE;
+        }
+        if config.timer {
+            data |= BitFlags::TIE;
+        }
+        if config.timer_output == InterruptOutput::Pulsating {
+            data |= BitFlags::TI_TP;
+        }
+        self.write_register(Register::CTRL_STATUS_2, data)?;
+        self.timer_mode = match config.timer_output {
+            InterruptOutput::Continuous => TimerMode::OneShot,
+            InterruptOutput::Pulsating => TimerMode::Periodic,
+        };
+        Ok(())
+    }
+
+    /// Read the interrupt configuration.
+    pub fn get_interrupt_config(&mut self) -> Result<InterruptConfig, Error<E>> {
+        let data = self.read_register(Register::CTRL_STATUS_2)?;
+        Ok(InterruptConfig {
+            alarm: data & BitFlags::AIE != 0,
+            timer: data & BitFlags::TIE != 0,
+            timer_output: if data & BitFlags::TI_TP != 0 {
+                InterruptOutput::Pulsating
+            } else {
+                InterruptOutput::Continuous
+            },
+        })
+    }
+
+    /// Read the raw values of both control registers in one transaction.
+    pub fn read_control_registers(&mut self) -> Result<[u8; 2], Error<E>> {
+        let mut data = [0; 2];
+        self.read_registers(Register::CTRL_STATUS_1, &mut data)?;
+        Ok(data)
+    }
+
+    /// Write the raw values of both control registers in one transaction.
+    ///
+    /// The values are written as given: writing 1 to the TEST1 bit stops the normal timekeeping,
+    /// and writing 0 to the AF or TF bit clears the flag.
+    pub fn write_control_registers(&mut self, data: [u8; 2]) -> Result<(), Error<E>> {
+        self.write_registers(Register::CTRL_STATUS_1, &data)
+    }
+
+    /// Read both control registers in one transaction.
+    pub fn status(&mut self) -> Result<Status, Error<E>> {
+        let data = self.read_control_registers()?;
+        Ok(Status {
+            stopped: data[0] & BitFlags::STOP != 0,
+            ext_clk_test_mode: data[0] & BitFlags::TEST1 != 0,
+            power_on_reset_override: data[0] & BitFlags::TESTC != 0,
+            pulse_mode: data[1] & BitFlags::TI_TP != 0,
+            alarm_flag: data[1] & BitFlags::AF != 0,
+            timer_flag: data[1] & BitFlags::TF != 0,
+            alarm_interrupt_enabled: data[1] & BitFlags::AIE != 0,
+            timer_interrupt_enabled: data[1] & BitFlags::TIE != 0,
+        })
+    }
+
+    /// Reset all the registers to their power-on values, without a power cycle, in three
+    /// transactions: stop the clock, which also resets the prescaler, write the registers,
+    /// then start the clock again.
+    ///
+    /// The date and time are set to 2000-01-01 00:00:00 (Saturday) with the voltage low flag set,
+    /// as they can't be trusted, the alarms and timer are disabled, the clock output is enabled
+    /// at 32768 Hz and both test modes are disabled. Use it to recover a chip in an undefined state,
+    /// `rtc_init()` is enough otherwise.
+    pub fn software_reset(&mut self) -> Result<(), Error<E>> {
+        self.write_register(Register::CTRL_STATUS_1, BitFlags::STOP)?;
+        self.write_registers(
+            Register::CTRL_STATUS_2,
+            &[
+                0,                              // interrupts disabled, flags cleared
+                BitFlags::VL,                   // seconds
+                0,                              // minutes
+                0,                              // hours
+                0x01,                           // day
+                0x06,                           // weekday
+                0x01,                           // month
+                0,                              // year
+                BitFlags::AE,                   // minute alarm
+                BitFlags::AE,                   // hour alarm
+                BitFlags::AE,                   // day alarm
+                BitFlags::AE,                   // weekday alarm
+                BitFlags::FE,                   // clock output enabled at 32768 Hz
+                TimerFreq::Timer_1_60Hz.bits(), // timer stopped
+                0,                              // timer value
+            ],
+        )?;
+        self.timer_mode = TimerMode::OneShot;
+        self.write_register(Register::CTRL_STATUS_1, 0)
+    }
+
     /// Initialize the RTC by setting all the control flags to zero, disabling alarms and timer, and setting the timer to the lowest frequency for power saving.
+    ///
+    /// The clock output is not changed, see `rtc_init_with()`.
     pub fn rtc_init(&mut self) -> Result<(), Error<E>> {
+        self.rtc_init_with(&InitConfig::default())
+    }
+
+    /// Same as `rtc_init()`, also disabling or enabling the clock output as configured.
+    pub fn rtc_init_with(&mut self, config: &InitConfig) -> Result<(), Error<E>> {
         self.write_register(Register::CTRL_STATUS_1, 0)?; // clear all the control bits
         self.write_register(Register::CTRL_STATUS_2, 0)?;
         self.clear_voltage_low_flag()?; // clear the low voltage flag
         self.disable_all_alarms()?; // disable alarm for all the components
-        self.set_timer_frequency(TimerFreq::Timer_1_60Hz)?; // set timer frequency to 1/60 Hz
-        Ok(())
+        self.write_register(Register::TIMER_CTRL, TimerFreq::Timer_1_60Hz.bits())?; // stop the timer, set frequency to 1/60 Hz
+        match config.clkout {
+            ClkoutInit::Leave => Ok(()),
+            ClkoutInit::Disable => self.control_clkout(Control::Off),
+            ClkoutInit::Enable(frequency) => self.set_clkout(frequency, Control::On),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::I2cMock;
+
+    #[test]
+    fn handle_interrupt_clears_only_set_flags() {
+        let mut i2c = I2cMock::new();
+        // AF and AIE set
+        i2c.set_read_data(&[0x0a]);
+        let mut rtc = PCF8563::new(i2c);
+        assert_eq!(rtc.handle_interrupt().unwrap(), InterruptSource::Alarm);
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x06]);
+    }
+
+    #[test]
+    fn service_calls_handlers_of_set_flags() {
+        let mut i2c = I2cMock::new();
+        // TF and TIE set
+        i2c.set_read_data(&[0x05]);
+        let mut rtc = PCF8563::new(i2c);
+        let (mut alarms, mut timers) = (0, 0);
+        let source = rtc.service(|| alarms += 1, || timers += 1).unwrap();
+        assert_eq!(source, InterruptSource::Timer);
+        assert_eq!((alarms, timers), (0, 1));
+    }
+
+    #[test]
+    fn can_set_and_get_interrupt_config() {
+        let config = InterruptConfig {
+            alarm: true,
+            timer: false,
+            timer_output: InterruptOutput::Pulsating,
+        };
+        let mut i2c = I2cMock::new();
+        // AF set as well
+        i2c.set_read_data(&[0x1a]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.set_interrupt_config(&config).unwrap();
+        assert_eq!(rtc.get_timer_mode(), TimerMode::Periodic);
+        assert_eq!(rtc.get_interrupt_config().unwrap(), config);
+        let mut rtc = PCF8563::new(rtc.destroy());
+        rtc.set_interrupt_config(&config).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x1e]);
+    }
+
+    #[test]
+    fn handle_interrupt_without_flags() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x03]);
+        let mut rtc = PCF8563::new(i2c);
+        assert_eq!(rtc.handle_interrupt().unwrap(), InterruptSource::None);
+    }
+
+    #[test]
+    fn can_clear_all_interrupt_flags() {
+        let mut i2c = I2cMock::new();
+        // TI_TP, AF, TF and TIE set
+        i2c.set_read_data(&[0x1d]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.clear_all_interrupt_flags().unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x11]);
+    }
+
+    #[test]
+    fn init_can_disable_clkout() {
+        let mut i2c = I2cMock::new();
+        // seconds with VL set, alarms, then clock output enabled at 1 Hz
+        i2c.set_read_data(&[0x80, 0, 0, 0, 0, 0x83]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.rtc_init_with(&InitConfig {
+            clkout: ClkoutInit::Disable,
+        })
+        .unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CLKOUT_CTRL, 0x03]);
+    }
+
+    #[test]
+    fn can_read_status() {
+        let mut i2c = I2cMock::new();
+        // STOP set, then TF and AIE set
+        i2c.set_read_data(&[0x20, 0x06]);
+        let mut rtc = PCF8563::new(i2c);
+        assert_eq!(
+            rtc.status().unwrap(),
+            Status {
+                stopped: true,
+                ext_clk_test_mode: false,
+                power_on_reset_override: false,
+                pulse_mode: false,
+                alarm_flag: false,
+                timer_flag: true,
+                alarm_interrupt_enabled: true,
+                timer_interrupt_enabled: false,
+            }
+        );
+    }
+
+    #[test]
+    fn can_clear_all_flags() {
+        let mut i2c = I2cMock::new();
+        // AF, TF and AIE set, then seconds with VL set
+        i2c.set_read_data(&[0x0e, 0x95]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.clear_all_flags().unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::VL_SECONDS, 0x15]);
+    }
+
+    #[test]
+    fn software_reset_restarts_clock() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        rtc.software_reset().unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_1, 0x00]);
+    }
+
+    #[test]
+    fn can_clear_test_modes() {
+        let mut i2c = I2cMock::new();
+        // TEST1, STOP and TESTC set
+        i2c.set_read_data(&[0xa8]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.clear_test_modes().unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_1, 0x20]);
+    }
+
+    #[test]
+    fn can_write_control_registers() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        rtc.write_control_registers([0x20, 0x1f]).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_1, 0x20, 0x1f]);
+    }
+
+    #[cfg(feature = "danger")]
+    #[test]
+    fn can_enter_external_clock_mode() {
+        let datetime = DateTime {
+            year: 25,
+            month: 6,
+            weekday: 3,
+            day: 4,
+            hours: 12,
+            minutes: 0,
+            seconds: 0,
+        };
+        let mut rtc = PCF8563::new(I2cMock::new());
+        rtc.enter_external_clock_mode(&datetime).unwrap();
+        rtc.exit_external_clock_mode().unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_1, 0x00]);
     }
 }
@@ -0,0 +1,121 @@
+//! `embedded-hal` countdown timer on top of the RTC timer (requires the `countdown` feature).
+//!
+//! The timer reloads itself when it reaches zero, so the countdown is periodic: once `wait()`
+//! returned, it returns again after the next period without calling `start()`.
+
+use super::{hal, Control, Error, PCF8563};
+use core::time::Duration;
+use hal::blocking::i2c::{Write, WriteRead};
+use hal::timer::{Cancel, CountDown, Periodic};
+use void::Void;
+
+/// Countdown timer using the RTC timer, with durations up to 255 minutes.
+///
+/// The traits can't report I2C errors: if `start()` or `wait()` fails, `wait()` returns
+/// immediately, and the error can be retrieved with `take_error()`.
+#[derive(Debug)]
+pub struct RtcCountDown<I2C, E> {
+    rtc: PCF8563<I2C>,
+    error: Option<Error<E>>,
+}
+
+impl<I2C, E> RtcCountDown<I2C, E>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Create a new countdown timer from the RTC driver.
+    pub fn new(rtc: PCF8563<I2C>) -> Self {
+        RtcCountDown { rtc, error: None }
+    }
+
+    /// Destroy the countdown timer, return the RTC driver.
+    pub fn destroy(self) -> PCF8563<I2C> {
+        self.rtc
+    }
+
+    /// Take the last error of `start()` or `wait()`, if any.
+    pub fn take_error(&mut self) -> Option<Error<E>> {
+        self.error.take()
+    }
+
+    fn restart(&mut self, duration: Duration) -> Result<(), Error<E>> {
+        self.rtc.stop_and_clear_timer(false)?;
+        self.rtc.set_timer_duration(duration)?;
+        self.rtc.control_timer(Control::On)
+    }
+}
+
+impl<I2C, E> CountDown for RtcCountDown<I2C, E>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    type Time = Duration;
+
+    /// Start the countdown, the duration is rounded to the nearest timer tick.
+    fn start<T>(&mut self, count: T)
+    where
+        T: Into<Duration>,
+    {
+        self.error = self.restart(count.into()).err();
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Void> {
+        if self.error.is_some() {
+            return Ok(());
+        }
+        match self.rtc.poll_timer() {
+            Err(nb::Error::Other(error)) => {
+                self.error = Some(error);
+                Ok(())
+            }
+            Err(nb::Error::WouldBlock) => Err(nb::Error::WouldBlock),
+            Ok(()) => Ok(()),
+        }
+    }
+}
+
+impl<I2C, E> Periodic for RtcCountDown<I2C, E> where I2C: Write<Error = E> + WriteRead<Error = E> {}
+
+impl<I2C, E> Cancel for RtcCountDown<I2C, E>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    type Error = Error<E>;
+
+    /// Stop the timer and clear the timer flag.
+    fn cancel(&mut self) -> Result<(), Error<E>> {
+        self.rtc.stop_and_clear_timer(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Register;
+    use embedded_hal_mock::I2cMock;
+
+    #[test]
+    fn waits_for_timer_flag() {
+        let mut i2c = I2cMock::new();
+        // TF not set, then set
+        i2c.set_read_data(&[0x00, 0x04, 0x04]);
+        let mut countdown = RtcCountDown::new(PCF8563::new(i2c));
+        assert_eq!(countdown.wait(), Err(nb::Error::WouldBlock));
+        assert_eq!(countdown.wait(), Ok(()));
+        assert!(countdown.take_error().is_none());
+        let i2c = countdown.destroy().destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x08]);
+    }
+
+    #[test]
+    fn start_reports_invalid_duration() {
+        let i2c = I2cMock::new();
+        let mut countdown = RtcCountDown::new(PCF8563::new(i2c));
+        countdown.start(Duration::from_secs(0));
+        assert_eq!(countdown.wait(), Ok(()));
+        assert!(matches!(
+            countdown.take_error(),
+            Some(Error::InvalidInputData)
+        ));
+    }
+}
@@ -0,0 +1,376 @@
+//! Alarm schedules given as a restricted cron expression.
+//!
+//! The expression has four fields: `minute hour day-of-month day-of-week`, e.g. `30 7 * 1-5`
+//! for 07:30 from Monday to Friday. Each field is either `*`, a value, a range `a-b`,
+//! optionally followed by a step `/n`, or a comma-separated list of those.
+//! The weekday is numbered from Sunday (0), 7 is accepted for Sunday as well.
+//!
+//! As in cron, when both the day of the month and the day of the week are restricted,
+//! the schedule matches when either of them matches.
+//!
+//! Schedules which the alarm registers can express directly (e.g. `0 7 * *`) are
+//! programmed once, the others are programmed one occurrence at a time.
+
+use super::{
+    calendar, hal, AlarmConfig, Control, DateTime, Error, OutOfRangeError, YearMapping, PCF8563,
+};
+use core::str::FromStr;
+use hal::blocking::i2c::{Write, WriteRead};
+
+/// Number of days searched for the next occurrence, enough for e.g. the 31st of the month.
+const SEARCH_DAYS: i64 = 62;
+
+const ALL_HOURS: u32 = (1 << 24) - 1;
+const ALL_DAYS: u32 = !1;
+const ALL_WEEKDAYS: u8 = (1 << 7) - 1;
+
+/// Error returned when a cron expression can't be parsed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CronParseError;
+
+/// Schedule parsed from a cron expression, see the module documentation for the syntax.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    weekdays: u8,
+}
+
+impl FromStr for CronSchedule {
+    type Err = CronParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.split_whitespace();
+        let mut next = |min, max| parse_field(fields.next().ok_or(CronParseError)?, min, max);
+        let minutes = next(0, 59)?;
+        let hours = next(0, 23)? as u32;
+        let days = next(1, 31)? as u32;
+        let weekdays = next(0, 7)?;
+        if fields.next().is_some() {
+            return Err(CronParseError);
+        }
+        // 7 is Sunday as well
+        let weekdays = ((weekdays | weekdays >> 7) as u8) & ALL_WEEKDAYS;
+        Ok(CronSchedule {
+            minutes,
+            hours,
+            days,
+            weekdays,
+        })
+    }
+}
+
+impl CronSchedule {
+    /// Check if the date and time match the schedule, the seconds are ignored.
+    pub fn matches(&self, datetime: &DateTime) -> bool {
+        has_bit(self.minutes, datetime.minutes)
+            && has_bit(u64::from(self.hours), datetime.hours)
+            && self.matches_day(datetime.day, datetime.weekday)
+    }
+
+    /// Alarm setting matching exactly the schedule, if the alarm registers can express it.
+    ///
+    /// This is the case when the minute is a single value, and the hour, day of the month
+    /// and day of the week are either `*` or a single value, not both days restricted.
+    pub fn to_alarm_config(&self) -> Option<AlarmConfig> {
+        let days_any = self.days == ALL_DAYS;
+        let weekdays_any = self.weekdays == ALL_WEEKDAYS;
+        if !days_any && !weekdays_any {
+            return None;
+        }
+        Some(AlarmConfig {
+            minutes: Some(single(self.minutes)?),
+            hours: if self.hours == ALL_HOURS {
+                None
+            } else {
+                Some(single(u64::from(self.hours))?)
+            },
+            day: if days_any {
+                None
+            } else {
+                Some(single(u64::from(self.days))?)
+            },
+            weekday: if weekdays_any {
+                None
+            } else {
+                Some(single(u64::from(self.weekdays))?)
+            },
+        })
+    }
+
+    /// Date and time of the first occurrence strictly after the given one.
+    ///
+    /// Will return an `OutOfRangeError` if there is no occurrence in the next 62 days,
+    /// e.g. for the 31st of February, or after the year 2099.
+    pub fn next_after(&self, datetime: &DateTime) -> Result<DateTime, OutOfRangeError> {
+        self.next_after_with(datetime, YearMapping::default())
+    }
+
+    /// Same as `next_after()`, mapping the year as given.
+    ///
+    /// Will return an `OutOfRangeError` if there is no occurrence in the next 62 days,
+    /// or if it can't be represented with the mapping.
+    pub fn next_after_with(
+        &self,
+        datetime: &DateTime,
+        mapping: YearMapping,
+    ) -> Result<DateTime, OutOfRangeError> {
+        let now = datetime.to_unix_timestamp_with(mapping)?;
+        let today = now.div_euclid(calendar::SECONDS_PER_DAY);
+        for days in today..=today + SEARCH_DAYS {
+            let (_, _, day) = calendar::civil_from_days(days);
+            if !self.matches_day(day, calendar::weekday_from_days(days)) {
+                continue;
+            }
+            let midnight = days * calendar::SECONDS_PER_DAY;
+            for hours in (0..24).filter(|hours| self.hours & (1 << hours) != 0) {
+                for minutes in (0..60).filter(|minutes| self.minutes & (1 << minutes) != 0) {
+                    let timestamp = midnight + hours * 3600 + minutes * 60;
+                    if timestamp > now {
+                        return DateTime::from_unix_timestamp_with(timestamp, mapping);
+                    }
+                }
+            }
+        }
+        Err(OutOfRangeError)
+    }
+
+    /// Clear the alarm flag, set the alarm and enable the alarm interrupt.
+    ///
+    /// Returns the date and time of the next occurrence.
+    pub fn start<I2C, E>(&self, rtc: &mut PCF8563<I2C>) -> Result<DateTime, Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        rtc.clear_alarm_flag()?;
+        let now = rtc.get_datetime()?;
+        let target = self.arm(rtc, &now)?;
+        rtc.control_alarm_interrupt(Control::On)?;
+        Ok(target)
+    }
+
+    /// Check the alarm flag and, if set, clear it and set the alarm to the next occurrence.
+    ///
+    /// Returns the date and time of the next occurrence if the schedule was due, or `None`
+    /// if the flag was not set or the alarm was set off by a day of a different month.
+    pub fn service<I2C, E>(&self, rtc: &mut PCF8563<I2C>) -> Result<Option<DateTime>, Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        if !rtc.get_alarm_flag()? {
+            return Ok(None);
+        }
+        rtc.clear_alarm_flag()?;
+        let now = rtc.get_datetime()?;
+        let target = self.arm(rtc, &now)?;
+        Ok(if self.matches(&now) {
+            Some(target)
+        } else {
+            None
+        })
+    }
+
+    fn arm<I2C, E>(&self, rtc: &mut PCF8563<I2C>, now: &DateTime) -> Result<DateTime, Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        let target = self.next_after_with(now, rtc.get_year_mapping())?;
+        match self.to_alarm_config() {
+            Some(alarm) => rtc.set_alarm(&alarm)?,
+            None => rtc.set_alarm_at(&target)?,
+        }
+        Ok(target)
+    }
+
+    fn matches_day(&self, day: u8, weekday: u8) -> bool {
+        let day_matches = has_bit(u64::from(self.days), day);
+        let weekday_matches = has_bit(u64::from(self.weekdays), weekday);
+        match (self.days == ALL_DAYS, self.weekdays == ALL_WEEKDAYS) {
+            (false, false) => day_matches || weekday_matches,
+            _ => day_matches && weekday_matches,
+        }
+    }
+}
+
+/// Check if the bit is set in the mask, `false` for out of range bits.
+fn has_bit(mask: u64, bit: u8) -> bool {
+    bit < 64 && mask & (1 << bit) != 0
+}
+
+/// Value of a bitmask with a single bit set.
+fn single(mask: u64) -> Option<u8> {
+    if mask.count_ones() == 1 {
+        Some(mask.trailing_zeros() as u8)
+    } else {
+        None
+    }
+}
+
+/// Parse a field into a bitmask of the values.
+fn parse_field(field: &str, min: u8, max: u8) -> Result<u64, CronParseError> {
+    let mut mask = 0;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, parse_value(step, 1, max)?),
+            None => (item, 1),
+        };
+        let (first, last) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((first, last)) => {
+                    (parse_value(first, min, max)?, parse_value(last, min, max)?)
+                }
+                None => {
+                    let value = parse_value(range, min, max)?;
+                    (value, value)
+                }
+            },
+        };
+        if first > last {
+            return Err(CronParseError);
+        }
+        for value in (first..=last).step_by(usize::from(step)) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_value(value: &str, min: u8, max: u8) -> Result<u8, CronParseError> {
+    match value.parse() {
+        Ok(value) if (min..=max).contains(&value) => Ok(value),
+        _ => Err(CronParseError),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::I2cMock;
+
+    const FRIDAY: DateTime = DateTime {
+        year: 21,
+        month: 4,
+        day: 30,
+        weekday: 5,
+        hours: 7,
+        minutes: 30,
+        seconds: 0,
+    };
+
+    #[test]
+    fn can_parse_expressions() {
+        let schedule: CronSchedule = "*/15 8-17 * 1-5".parse().unwrap();
+        assert!(schedule.matches(&DateTime {
+            hours: 8,
+            minutes: 45,
+            ..FRIDAY
+        }));
+        assert!(!schedule.matches(&FRIDAY));
+        let sunday: CronSchedule = "0 0 * 7".parse().unwrap();
+        assert_eq!(sunday, "0 0 * 0".parse().unwrap());
+    }
+
+    #[test]
+    fn cannot_parse_invalid_expressions() {
+        for expression in [
+            "",
+            "0 0 *",
+            "60 0 * *",
+            "0 0 0 *",
+            "0 0 * * *",
+            "5-1 * * *",
+            "a * * *",
+        ] {
+            assert_eq!(expression.parse::<CronSchedule>(), Err(CronParseError));
+        }
+    }
+
+    #[test]
+    fn maps_simple_expressions_to_alarm() {
+        let schedule: CronSchedule = "30 7 * 1".parse().unwrap();
+        assert_eq!(
+            schedule.to_alarm_config(),
+            Some(AlarmConfig {
+                minutes: Some(30),
+                hours: Some(7),
+                day: None,
+                weekday: Some(1),
+            })
+        );
+        let schedule: CronSchedule = "30 7 1 1".parse().unwrap();
+        assert_eq!(schedule.to_alarm_config(), None);
+        let schedule: CronSchedule = "0,30 * * *".parse().unwrap();
+        assert_eq!(schedule.to_alarm_config(), None);
+    }
+
+    #[test]
+    fn out_of_range_datetime_does_not_match() {
+        let schedule: CronSchedule = "* * * *".parse().unwrap();
+        assert!(schedule.matches(&FRIDAY));
+        for datetime in [
+            DateTime {
+                minutes: 85,
+                ..FRIDAY
+            },
+            DateTime {
+                hours: 45,
+                ..FRIDAY
+            },
+            DateTime { day: 0, ..FRIDAY },
+            DateTime { day: 99, ..FRIDAY },
+            DateTime {
+                weekday: 200,
+                ..FRIDAY
+            },
+        ] {
+            assert!(!schedule.matches(&datetime));
+        }
+    }
+
+    #[test]
+    fn computes_next_occurrence() {
+        let schedule: CronSchedule = "0 9 31 *".parse().unwrap();
+        let next = schedule.next_after(&FRIDAY).unwrap();
+        assert_eq!((next.month, next.day, next.hours), (5, 31, 9));
+        // 1st of the month or Monday
+        let schedule: CronSchedule = "0 6 1 1".parse().unwrap();
+        let next = schedule.next_after(&FRIDAY).unwrap();
+        assert_eq!((next.month, next.day), (5, 1));
+        let next = schedule.next_after(&next).unwrap();
+        assert_eq!((next.month, next.day), (5, 3));
+    }
+
+    #[test]
+    fn computes_next_occurrence_with_year_mapping() {
+        let schedule: CronSchedule = "0 0 29 *".parse().unwrap();
+        let february = DateTime {
+            year: 0,
+            month: 2,
+            day: 28,
+            ..FRIDAY
+        };
+        let next = schedule
+            .next_after_with(&february, YearMapping::default())
+            .unwrap();
+        assert_eq!((next.month, next.day), (2, 29));
+        // 2100 is not a leap year
+        let mapping = YearMapping::new(2100, 100).unwrap();
+        let next = schedule.next_after_with(&february, mapping).unwrap();
+        assert_eq!((next.month, next.day), (3, 29));
+    }
+
+    #[test]
+    fn sets_next_occurrence_when_not_expressible() {
+        let schedule: CronSchedule = "0,30 12 * *".parse().unwrap();
+        let mut i2c = I2cMock::new();
+        // AF set, 2021-04-30 12:00:02
+        i2c.set_read_data(&[0x08, 0x08, 0x02, 0x00, 0x12, 0x30, 0x05, 0x04, 0x21]);
+        let mut rtc = PCF8563::new(i2c);
+        let next = schedule.service(&mut rtc).unwrap().unwrap();
+        assert_eq!((next.hours, next.minutes), (12, 30));
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[0x09, 0x30, 0x12, 0x30, 0x80]);
+    }
+}
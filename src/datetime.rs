@@ -6,11 +6,19 @@
 //! TO DO: As the chip may be used for devices that are clocks only, without the calendar function
 //! a convenient set_time() function could be added (sets only seconds, minutes and hours)
 
-use super::{decode_bcd, encode_bcd, hal, BitFlags, Error, Register, DEVICE_ADDRESS, PCF8563};
+use super::{
+    calendar, decode_bcd, encode_bcd, hal, BitFlags, Control, Error, Register, DEVICE_ADDRESS,
+    PCF8563,
+};
+use core::convert::TryFrom;
 use hal::blocking::i2c::{Write, WriteRead};
 
+/// Maximum number of additional reads done by `get_datetime_consistent()`.
+const CONSISTENT_READ_ATTEMPTS: u8 = 4;
+
 /// Container to hold date and time components.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DateTime {
     /// Year [0-99].
     pub year: u8,
@@ -28,8 +36,249 @@ pub struct DateTime {
     pub seconds: u8,
 }
 
+impl DateTime {
+    /// Check if all the components are within their ranges, including the number of days in the month.
+    ///
+    /// The year is taken as 2000-2099.
+    pub fn is_valid(&self) -> bool {
+        self.is_valid_with(YearMapping::default())
+    }
+
+    /// Same as `is_valid()`, mapping the year as given for the number of days in February.
+    pub fn is_valid_with(&self, mapping: YearMapping) -> bool {
+        let year = match mapping.full_year(self.year) {
+            Some(year) => i32::from(year),
+            None => return false,
+        };
+        (1..=12).contains(&self.month)
+            && self.weekday <= 6
+            && self.day >= 1
+            && self.day <= calendar::days_in_month(year, self.month)
+            && self.hours <= 23
+            && self.minutes <= 59
+            && self.seconds <= 59
+    }
+
+    /// Decode the time registers, from the seconds to the years.
+    pub(crate) fn from_registers(data: &[u8; 7]) -> Self {
+        DateTime {
+            year: decode_bcd(data[6]),
+            month: decode_bcd(data[5] & 0x1f),
+            weekday: decode_bcd(data[4] & 0x07),
+            day: decode_bcd(data[3] & 0x3f),
+            hours: decode_bcd(data[2] & 0x3f),
+            minutes: decode_bcd(data[1] & 0x7f),
+            seconds: decode_bcd(data[0]),
+        }
+    }
+
+    /// Convert to the number of seconds since 1970-01-01 00:00:00, the year is taken as 2000-2099.
+    ///
+    /// Will return an `OutOfRangeError` if any of the components is out of range.
+    pub fn to_unix_timestamp(&self) -> Result<i64, OutOfRangeError> {
+        self.to_unix_timestamp_with(YearMapping::default())
+    }
+
+    /// Convert to the number of seconds since 1970-01-01 00:00:00, mapping the year as given.
+    ///
+    /// Will return an `OutOfRangeError` if any of the components is out of range.
+    pub fn to_unix_timestamp_with(&self, mapping: YearMapping) -> Result<i64, OutOfRangeError> {
+        if !self.is_valid_with(mapping) {
+            return Err(OutOfRangeError);
+        }
+        let year = i32::from(mapping.full_year(self.year).ok_or(OutOfRangeError)?);
+        let days = calendar::days_from_civil(year, self.month, self.day);
+        Ok(days * calendar::SECONDS_PER_DAY
+            + i64::from(self.hours) * 3600
+            + i64::from(self.minutes) * 60
+            + i64::from(self.seconds))
+    }
+
+    /// Create from the number of seconds since 1970-01-01 00:00:00, the weekday is set counting from Sunday (0).
+    ///
+    /// Will return an `OutOfRangeError` if the year is outside of the range 2000-2099.
+    pub fn from_unix_timestamp(timestamp: i64) -> Result<Self, OutOfRangeError> {
+        DateTime::from_unix_timestamp_with(timestamp, YearMapping::default())
+    }
+
+    /// Create from the number of seconds since 1970-01-01 00:00:00, mapping the year as given.
+    ///
+    /// Will return an `OutOfRangeError` if the year can't be represented with the mapping.
+    pub fn from_unix_timestamp_with(
+        timestamp: i64,
+        mapping: YearMapping,
+    ) -> Result<Self, OutOfRangeError> {
+        let days = timestamp.div_euclid(calendar::SECONDS_PER_DAY);
+        let seconds = timestamp.rem_euclid(calendar::SECONDS_PER_DAY);
+        let (year, month, day) = calendar::civil_from_days(days);
+        Ok(DateTime {
+            year: mapping.two_digit_year(year).ok_or(OutOfRangeError)?,
+            month,
+            weekday: calendar::weekday_from_days(days),
+            day,
+            hours: (seconds / 3600) as u8,
+            minutes: (seconds % 3600 / 60) as u8,
+            seconds: (seconds % 60) as u8,
+        })
+    }
+
+    /// Convert to an NTP timestamp (seconds since 1900-01-01 00:00:00), the year is taken as 2000-2099.
+    ///
+    /// Times after 2036-02-07 06:28:15 wrap around into NTP era 1.
+    /// Will return an `OutOfRangeError` if any of the components is out of range.
+    pub fn to_ntp_seconds(&self) -> Result<u32, OutOfRangeError> {
+        self.to_ntp_seconds_with(YearMapping::default())
+    }
+
+    /// Convert to an NTP timestamp (seconds since 1900-01-01 00:00:00), mapping the year as given.
+    ///
+    /// Will return an `OutOfRangeError` if any of the components is out of range,
+    /// or if the time is outside of the range 1968-2104 which can be told apart
+    /// from the timestamp alone.
+    pub fn to_ntp_seconds_with(&self, mapping: YearMapping) -> Result<u32, OutOfRangeError> {
+        let ntp = self.to_unix_timestamp_with(mapping)? + NTP_UNIX_OFFSET;
+        if !(NTP_ERA_PIVOT..NTP_ERA_PIVOT + NTP_ERA_LENGTH).contains(&ntp) {
+            return Err(OutOfRangeError);
+        }
+        Ok(ntp as u32)
+    }
+
+    /// Create from an NTP timestamp (seconds since 1900-01-01 00:00:00), the year is taken as 2000-2099.
+    ///
+    /// Will return an `OutOfRangeError` if the year is outside of the range 2000-2099.
+    pub fn from_ntp_seconds(seconds: u32) -> Result<Self, OutOfRangeError> {
+        DateTime::from_ntp_seconds_with(seconds, YearMapping::default())
+    }
+
+    /// Create from an NTP timestamp (seconds since 1900-01-01 00:00:00), mapping the year as given.
+    ///
+    /// As recommended by RFC 4330, timestamps with the most significant bit cleared
+    /// are taken to be in NTP era 1 (2036-2104), the others in era 0 (1968-2036).
+    /// Will return an `OutOfRangeError` if the year can't be represented with the mapping.
+    pub fn from_ntp_seconds_with(
+        seconds: u32,
+        mapping: YearMapping,
+    ) -> Result<Self, OutOfRangeError> {
+        let mut ntp = i64::from(seconds);
+        if ntp < NTP_ERA_PIVOT {
+            ntp += NTP_ERA_LENGTH;
+        }
+        DateTime::from_unix_timestamp_with(ntp - NTP_UNIX_OFFSET, mapping)
+    }
+}
+
+/// Seconds between the NTP epoch (1900-01-01) and the Unix epoch (1970-01-01).
+const NTP_UNIX_OFFSET: i64 = 2_208_988_800;
+
+/// Length of an NTP era in seconds.
+const NTP_ERA_LENGTH: i64 = 1 << 32;
+
+/// NTP timestamps below this value (MSB cleared) belong to era 1.
+const NTP_ERA_PIVOT: i64 = 1 << 31;
+
+/// Handling of the voltage low flag (VL) when the date and time are set.
+///
+/// The VL flag is stored in the seconds register, so writing the seconds
+/// always writes the flag as well.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VoltageLowHandling {
+    /// Clear the flag (default behavior of `set_datetime()`).
+    Clear,
+    /// Keep the flag as it is, so that a power loss can still be detected later.
+    Preserve,
+    /// Do not write anything and return an error if the flag is set.
+    FailIfSet,
+}
+
+/// Behavior when the voltage low flag (VL) is found set by `get_datetime()` or `check_power()`,
+/// i.e. the backup supply failed and the date and time may be invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum VoltageLowPolicy {
+    /// Return the date and time as read (default).
+    #[default]
+    Ignore,
+    /// Return an 'Error::VoltageLow'.
+    ReportError,
+    /// Set the date and time to the default, clearing the flag, and return it.
+    ResetToDefault(DateTime),
+}
+
+/// Mapping of the two-digit year of the RTC to a full year.
+///
+/// Two-digit years below the pivot are mapped to `base + year`, the others to
+/// `base - 100 + year`. For example, with base 2000 and pivot 70, the years 00-69
+/// are mapped to 2000-2069 and the years 70-99 to 1970-1999.
+///
+/// Note that the RTC itself considers every year divisible by 4 a leap year.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct YearMapping {
+    base: u16,
+    pivot: u8,
+}
+
+impl YearMapping {
+    /// Years 2000-2099 (default).
+    pub const Y2000: YearMapping = YearMapping {
+        base: 2000,
+        pivot: 100,
+    };
+
+    /// Create a new mapping from the base year and the pivot [0-100].
+    ///
+    /// Will return an `OutOfRangeError` if the pivot is above 100, or the base is below 100
+    /// or above 65436, so that all the full years fit in a `u16`.
+    pub fn new(base: u16, pivot: u8) -> Result<Self, OutOfRangeError> {
+        if pivot > 100 || !(100..=u16::MAX - 99).contains(&base) {
+            return Err(OutOfRangeError);
+        }
+        Ok(YearMapping { base, pivot })
+    }
+
+    /// Full year corresponding to the two-digit year [0-99], `None` if out of range.
+    pub fn full_year(&self, year: u8) -> Option<u16> {
+        if year > 99 {
+            None
+        } else if year < self.pivot {
+            Some(self.base + u16::from(year))
+        } else {
+            Some(self.base - 100 + u16::from(year))
+        }
+    }
+
+    /// Two-digit year corresponding to the full year, `None` if out of range.
+    pub fn two_digit_year(&self, year: i32) -> Option<u8> {
+        let base = i32::from(self.base);
+        let pivot = i32::from(self.pivot);
+        if year >= base && year < base + pivot {
+            Some((year - base) as u8)
+        } else if year >= base - 100 + pivot && year < base {
+            Some((year - base + 100) as u8)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for YearMapping {
+    fn default() -> Self {
+        YearMapping::Y2000
+    }
+}
+
+/// Error returned when a date and time can't be converted to or from the RTC representation,
+/// e.g. because the year is outside of the range 2000-2099 (or of the `YearMapping` used).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutOfRangeError;
+
+impl<E> From<OutOfRangeError> for Error<E> {
+    fn from(_: OutOfRangeError) -> Self {
+        Error::InvalidInputData
+    }
+}
+
 /// Container to hold time components only (for clock applications without calendar functions).
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Time {
     /// Hours [0-23]
     pub hours: u8,
@@ -39,6 +288,55 @@ pub struct Time {
     pub seconds: u8,
 }
 
+/// Day of the week, numbered as in the RTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weekday {
+    /// Sunday (0).
+    Sunday = 0,
+    /// Monday (1).
+    Monday = 1,
+    /// Tuesday (2).
+    Tuesday = 2,
+    /// Wednesday (3).
+    Wednesday = 3,
+    /// Thursday (4).
+    Thursday = 4,
+    /// Friday (5).
+    Friday = 5,
+    /// Saturday (6).
+    Saturday = 6,
+}
+
+impl Weekday {
+    /// Bit of the weekday in a weekday bitmask (bit 0: Sunday).
+    pub const fn mask(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl From<Weekday> for u8 {
+    fn from(weekday: Weekday) -> Self {
+        weekday as u8
+    }
+}
+
+impl TryFrom<u8> for Weekday {
+    type Error = OutOfRangeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Weekday::Sunday),
+            1 => Ok(Weekday::Monday),
+            2 => Ok(Weekday::Tuesday),
+            3 => Ok(Weekday::Wednesday),
+            4 => Ok(Weekday::Thursday),
+            5 => Ok(Weekday::Friday),
+            6 => Ok(Weekday::Saturday),
+            _ => Err(OutOfRangeError),
+        }
+    }
+}
+
 impl<I2C, E> PCF8563<I2C>
 where
     I2C: Write<Error = E> + WriteRead<Error = E>,
@@ -49,36 +347,110 @@ where
         self.i2c
             .write_read(DEVICE_ADDRESS, &[Register::VL_SECONDS], &mut data)
             .map_err(Error::I2C)?;
-        Ok(DateTime {
-            year: decode_bcd(data[6]),
-            month: decode_bcd(data[5] & 0x1f),
-            weekday: decode_bcd(data[4] & 0x07),
-            day: decode_bcd(data[3] & 0x3f),
-            hours: decode_bcd(data[2] & 0x3f),
-            minutes: decode_bcd(data[1] & 0x7f),
-            seconds: decode_bcd(data[0]),
-        })
+        let datetime = DateTime::from_registers(&data);
+        if data[0] & BitFlags::VL != 0 {
+            Ok(self.apply_voltage_low_policy()?.unwrap_or(datetime))
+        } else {
+            Ok(datetime)
+        }
+    }
+
+    /// Set the behavior when the voltage low flag is found set by `get_datetime()`
+    /// (and all the functions reading the date and time) or `check_power()`.
+    pub fn set_voltage_low_policy(&mut self, policy: VoltageLowPolicy) {
+        self.voltage_low_policy = policy;
+    }
+
+    /// Get the behavior when the voltage low flag is found set.
+    pub fn get_voltage_low_policy(&self) -> VoltageLowPolicy {
+        self.voltage_low_policy
+    }
+
+    /// Read the voltage low flag and apply the voltage low policy if it is set,
+    /// returns whether it was set.
+    pub fn check_power(&mut self) -> Result<bool, Error<E>> {
+        let voltage_low = self.get_voltage_low_flag()?;
+        if voltage_low {
+            self.apply_voltage_low_policy()?;
+        }
+        Ok(voltage_low)
+    }
+
+    /// Apply the voltage low policy, returns the date and time written if any.
+    fn apply_voltage_low_policy(&mut self) -> Result<Option<DateTime>, Error<E>> {
+        match self.voltage_low_policy {
+            VoltageLowPolicy::Ignore => Ok(None),
+            VoltageLowPolicy::ReportError => Err(Error::VoltageLow),
+            VoltageLowPolicy::ResetToDefault(datetime) => {
+                self.set_datetime(&datetime)?;
+                Ok(Some(datetime))
+            }
+        }
+    }
+
+    /// Read date and time all at once, repeating the read until two consecutive
+    /// reads return the same value.
+    ///
+    /// Will return an 'Error::InconsistentRead' if no two consecutive reads match
+    /// within a few attempts.
+    pub fn get_datetime_consistent(&mut self) -> Result<DateTime, Error<E>> {
+        let mut previous = self.get_datetime()?;
+        for _ in 0..CONSISTENT_READ_ATTEMPTS {
+            let current = self.get_datetime()?;
+            if current == previous {
+                return Ok(current);
+            }
+            previous = current;
+        }
+        Err(Error::InconsistentRead)
     }
 
     /// Set date and time all at once.
     ///
+    /// The voltage low flag (VL) shares the register with the seconds and will be cleared,
+    /// use `set_datetime_with_vl()` to choose a different behavior.
+    ///
     /// Will return an 'Error::InvalidInputData' if any of the parameters is out of range.
     pub fn set_datetime(&mut self, datetime: &DateTime) -> Result<(), Error<E>> {
+        self.set_datetime_with_vl(datetime, VoltageLowHandling::Clear)
+    }
+
+    /// Set date and time all at once, handling the voltage low flag (VL) as requested.
+    ///
+    /// Will return an 'Error::InvalidInputData' if any of the parameters is out of range,
+    /// or an 'Error::VoltageLow' if the flag is set and `VoltageLowHandling::FailIfSet` was chosen.
+    pub fn set_datetime_with_vl(
+        &mut self,
+        datetime: &DateTime,
+        vl: VoltageLowHandling,
+    ) -> Result<(), Error<E>> {
         if datetime.year > 99
             || datetime.month < 1
             || datetime.month > 12
             || datetime.weekday > 6
             || datetime.day < 1
-            || datetime.month > 31
+            || datetime.day > 31
             || datetime.hours > 23
             || datetime.minutes > 59
             || datetime.seconds > 59
         {
             return Err(Error::InvalidInputData);
         }
+        let vl_bit = match vl {
+            VoltageLowHandling::Clear => 0,
+            VoltageLowHandling::Preserve => {
+                self.read_register(Register::VL_SECONDS)? & BitFlags::VL
+            }
+            VoltageLowHandling::FailIfSet => {
+                if self.get_voltage_low_flag()? {
+                    return Err(Error::VoltageLow);
+                }
+                0
+            }
+        };
         let payload = [
             Register::VL_SECONDS, //first register
-            encode_bcd(datetime.seconds),
+            encode_bcd(datetime.seconds) | vl_bit,
             encode_bcd(datetime.minutes),
             encode_bcd(datetime.hours),
             encode_bcd(datetime.day),
@@ -89,6 +461,38 @@ where
         self.i2c.write(DEVICE_ADDRESS, &payload).map_err(Error::I2C)
     }
 
+    /// Set date and time all at once and start the clock.
+    ///
+    /// Useful on the first boot, when the clock may have been stopped (STOP bit set).
+    pub fn set_datetime_and_start(&mut self, datetime: &DateTime) -> Result<(), Error<E>> {
+        self.set_datetime(datetime)?;
+        self.control_clock(Control::On)
+    }
+
+    /// Stop the clock, set date and time all at once, then start the clock with a single write
+    /// as soon as `wait` returns, e.g. on the edge of a GPS PPS signal.
+    ///
+    /// Stopping the clock resets the prescaler: according to the datasheet, the seconds
+    /// increment for the first time between 0.507813 and 0.507935 s after the clock is started.
+    /// To align the second boundaries with an external reference, write the time of its next
+    /// edge and return from `wait` about 0.492 s after the previous one.
+    ///
+    /// Will return an 'Error::InvalidInputData' if any of the parameters is out of range,
+    /// in which case nothing is written.
+    pub fn start_clock_at<F>(&mut self, datetime: &DateTime, wait: F) -> Result<(), Error<E>>
+    where
+        F: FnOnce(),
+    {
+        if !datetime.is_valid() {
+            return Err(Error::InvalidInputData);
+        }
+        let control = self.read_register(Register::CTRL_STATUS_1)?;
+        self.write_register(Register::CTRL_STATUS_1, control | BitFlags::STOP)?;
+        self.set_datetime(datetime)?;
+        wait();
+        self.write_register(Register::CTRL_STATUS_1, control & !BitFlags::STOP)
+    }
+
     /// Set only the time, date remains unchanged.
     ///
     /// Will return an 'Error::InvalidInputData' if any of the parameters is out of range.
@@ -105,6 +509,57 @@ where
         self.i2c.write(DEVICE_ADDRESS, &payload).map_err(Error::I2C)
     }
 
+    /// Set the mapping of the two-digit year to a full year, used by all the conversions
+    /// done by the driver (Unix timestamp, full year, external date and time types).
+    pub fn set_year_mapping(&mut self, mapping: YearMapping) {
+        self.year_mapping = mapping;
+    }
+
+    /// Get the mapping of the two-digit year to a full year.
+    pub fn get_year_mapping(&self) -> YearMapping {
+        self.year_mapping
+    }
+
+    /// Read the full year, according to the year mapping.
+    ///
+    /// Will return an 'Error::InvalidInputData' if the year read is out of range.
+    pub fn get_full_year(&mut self) -> Result<u16, Error<E>> {
+        let year = self.get_datetime()?.year;
+        Ok(self.year_mapping.full_year(year).ok_or(OutOfRangeError)?)
+    }
+
+    /// Read the date and time as the number of seconds since 1970-01-01 00:00:00,
+    /// according to the year mapping.
+    pub fn get_unix_timestamp(&mut self) -> Result<i64, Error<E>> {
+        let mapping = self.year_mapping;
+        Ok(self.get_datetime()?.to_unix_timestamp_with(mapping)?)
+    }
+
+    /// Set the date and time from the number of seconds since 1970-01-01 00:00:00,
+    /// according to the year mapping.
+    ///
+    /// Will return an 'Error::InvalidInputData' if the year can't be represented.
+    pub fn set_unix_timestamp(&mut self, timestamp: i64) -> Result<(), Error<E>> {
+        let datetime = DateTime::from_unix_timestamp_with(timestamp, self.year_mapping)?;
+        self.set_datetime(&datetime)
+    }
+
+    /// Read the date and time as an NTP timestamp (seconds since 1900-01-01 00:00:00),
+    /// according to the year mapping.
+    pub fn get_ntp_seconds(&mut self) -> Result<u32, Error<E>> {
+        let mapping = self.year_mapping;
+        Ok(self.get_datetime()?.to_ntp_seconds_with(mapping)?)
+    }
+
+    /// Set the date and time from an NTP timestamp (seconds since 1900-01-01 00:00:00),
+    /// e.g. the transmit timestamp of an SNTP reply, according to the year mapping.
+    ///
+    /// Will return an 'Error::InvalidInputData' if the year can't be represented.
+    pub fn set_ntp_seconds(&mut self, seconds: u32) -> Result<(), Error<E>> {
+        let datetime = DateTime::from_ntp_seconds_with(seconds, self.year_mapping)?;
+        self.set_datetime(&datetime)
+    }
+
     /// Read the century flag (0: century N, 1: century N+1).
     pub fn get_century_flag(&mut self) -> Result<u8, Error<E>> {
         let flag = self.is_register_bit_flag_high(Register::CENTURY_MONTHS, BitFlags::C)?;
@@ -120,7 +575,7 @@ where
         match century {
             0 => self.clear_register_bit_flag(Register::CENTURY_MONTHS, BitFlags::C),
             1 => self.set_register_bit_flag(Register::CENTURY_MONTHS, BitFlags::C),
-            _ => return Err(Error::InvalidInputData),
+            _ => Err(Error::InvalidInputData),
         }
     }
 }
@@ -0,0 +1,91 @@
+//! Wall-clock timestamps for the `defmt` log messages (requires the `defmt-timestamp` feature).
+//!
+//! The RTC can't be read over I2C from the logger, so it is read once at boot (and whenever
+//! convenient afterwards) to compute the Unix time at which the uptime counter of the
+//! application started. The timestamp of each message is then the uptime plus that offset:
+//!
+//! ```rust,ignore
+//! static WALL_CLOCK: DefmtTimestamp = DefmtTimestamp::new();
+//!
+//! defmt::timestamp!("{=u64:iso8601ms}", WALL_CLOCK.now_ms(uptime_ms()));
+//!
+//! // at boot, and e.g. once a day to follow the RTC
+//! WALL_CLOCK.seed(&mut rtc, uptime_ms()).unwrap();
+//! ```
+//!
+//! The RTC is expected to hold the UTC time. Until seeded, the timestamps are the uptime.
+//! As the RTC counts whole seconds, the timestamps are within a second of the RTC time.
+
+use super::{hal, Error, OutOfRangeError, PCF8563};
+use core::sync::atomic::{AtomicU32, Ordering};
+use hal::blocking::i2c::{Write, WriteRead};
+
+/// Offset between the uptime of the application and the Unix time, for the `defmt` timestamps.
+#[derive(Debug, Default)]
+pub struct DefmtTimestamp {
+    /// Unix time in seconds when the uptime was zero.
+    base: AtomicU32,
+}
+
+impl DefmtTimestamp {
+    /// Create a new offset, at zero until seeded.
+    pub const fn new() -> Self {
+        DefmtTimestamp {
+            base: AtomicU32::new(0),
+        }
+    }
+
+    /// Read the RTC and compute the offset from the current uptime in milliseconds.
+    ///
+    /// Will return an 'Error::InvalidInputData' if the RTC time is before the uptime
+    /// counter started or after 2106.
+    pub fn seed<I2C, E>(&self, rtc: &mut PCF8563<I2C>, uptime_ms: u64) -> Result<(), Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        let now = rtc.get_unix_timestamp()?;
+        Ok(self.set_unix_timestamp(now, uptime_ms)?)
+    }
+
+    /// Set the offset from the Unix time in seconds at the given uptime in milliseconds,
+    /// e.g. when the time is known from another source.
+    ///
+    /// Will return an `OutOfRangeError` if the time is before the uptime counter started
+    /// or after 2106.
+    pub fn set_unix_timestamp(
+        &self,
+        timestamp: i64,
+        uptime_ms: u64,
+    ) -> Result<(), OutOfRangeError> {
+        let base = timestamp - (uptime_ms / 1000) as i64;
+        if !(0..=i64::from(u32::MAX)).contains(&base) {
+            return Err(OutOfRangeError);
+        }
+        self.base.store(base as u32, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Unix time in milliseconds at the given uptime in milliseconds.
+    pub fn now_ms(&self, uptime_ms: u64) -> u64 {
+        u64::from(self.base.load(Ordering::Relaxed)) * 1000 + uptime_ms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::I2cMock;
+
+    #[test]
+    fn adds_uptime_to_rtc_time() {
+        let mut i2c = I2cMock::new();
+        // 2021-04-04 07:15:42
+        i2c.set_read_data(&[0x42, 0x15, 0x07, 0x04, 0x00, 0x04, 0x21]);
+        let mut rtc = PCF8563::new(i2c);
+        let timestamp = DefmtTimestamp::new();
+        assert_eq!(timestamp.now_ms(2_500), 2_500);
+        timestamp.seed(&mut rtc, 10_250).unwrap();
+        assert_eq!(timestamp.now_ms(10_250), 1_617_520_542_250);
+        assert_eq!(timestamp.now_ms(20_000), 1_617_520_552_000);
+    }
+}
@@ -0,0 +1,83 @@
+//! Timer and alarm functions taking `embassy_time` durations (requires the `embassy` feature).
+//!
+//! The actual duration of the timer is returned as an `embassy_time::Duration` as well,
+//! rounded to the nearest microsecond.
+//!
+//! ```rust,ignore
+//! use embassy_time::Duration;
+//!
+//! rtc.set_timer_embassy_duration(Duration::from_secs(90))?;
+//! rtc.control_timer(Control::On)?;
+//! rtc.wait_for_timer_embassy(Duration::from_millis(100)).await?;
+//! ```
+
+use super::{hal, DateTime, Error, PCF8563};
+use embassy_time::{Duration, Timer};
+use hal::blocking::i2c::{Write, WriteRead};
+
+/// Convert to `core::time::Duration`.
+fn to_core(duration: Duration) -> core::time::Duration {
+    core::time::Duration::from_micros(duration.as_micros())
+}
+
+/// Convert from `core::time::Duration`, rounding to the nearest microsecond.
+fn from_core(duration: core::time::Duration) -> Duration {
+    let micros = (duration.as_nanos() + 500) / 1_000;
+    Duration::from_micros(micros.min(u128::from(u64::MAX)) as u64)
+}
+
+impl<I2C, E> PCF8563<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Same as `set_timer_duration()`, with an `embassy_time` duration.
+    pub fn set_timer_embassy_duration(&mut self, duration: Duration) -> Result<Duration, Error<E>> {
+        self.set_timer_duration(to_core(duration)).map(from_core)
+    }
+
+    /// Same as `alarm_in()`, with an `embassy_time` duration.
+    pub fn alarm_in_embassy(&mut self, duration: Duration) -> Result<DateTime, Error<E>> {
+        self.alarm_in(to_core(duration))
+    }
+
+    /// Same as `wait_for_timer()`, waiting with `embassy_time::Timer` between the polls.
+    ///
+    /// For applications not using the interrupt pin. Waits forever if the timer is not running.
+    pub async fn wait_for_timer_embassy(
+        &mut self,
+        poll_interval: Duration,
+    ) -> Result<(), Error<E>> {
+        while !self.get_timer_flag()? {
+            Timer::after(poll_interval).await;
+        }
+        self.clear_timer_flag()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::I2cMock;
+
+    #[test]
+    fn can_convert_durations() {
+        assert_eq!(to_core(Duration::from_millis(1_500)).as_millis(), 1_500);
+        assert_eq!(
+            from_core(core::time::Duration::from_nanos(15_625_400)),
+            Duration::from_micros(15_625)
+        );
+    }
+
+    #[test]
+    fn can_set_timer_with_embassy_duration() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        let actual = rtc
+            .set_timer_embassy_duration(Duration::from_millis(50))
+            .unwrap();
+        assert_eq!(actual.as_micros(), 50_049);
+        let actual = rtc
+            .set_timer_embassy_duration(Duration::from_secs(300))
+            .unwrap();
+        assert_eq!(actual.as_secs(), 300);
+    }
+}
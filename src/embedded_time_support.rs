@@ -0,0 +1,101 @@
+//! Monotonic clock counting the clock output edges (requires the `embedded-time` feature).
+//!
+//! The clock output is wired to an interrupt pin of the microcontroller and the interrupt
+//! handler calls `ClkoutClock::tick()` on every edge, so that the clock runs off the RTC
+//! crystal rather than the microcontroller oscillator.
+//!
+//! ```rust,ignore
+//! static CLOCK: ClkoutClock<32> = ClkoutClock::new();
+//!
+//! CLOCK.start(&mut rtc).unwrap();
+//!
+//! // in the interrupt handler of the pin
+//! CLOCK.tick();
+//! ```
+
+use super::{hal, ClkoutFreq, Control, Error, PCF8563};
+use core::sync::atomic::{AtomicU32, Ordering};
+use embedded_time::{clock, fraction::Fraction, Clock, Instant};
+use hal::blocking::i2c::{Write, WriteRead};
+
+/// `embedded_time::Clock` counting the edges of the clock output at `HZ` [1, 32, 1024 or 32768].
+#[derive(Debug, Default)]
+pub struct ClkoutClock<const HZ: u32> {
+    ticks: AtomicU32,
+}
+
+impl<const HZ: u32> ClkoutClock<HZ> {
+    /// Create a new clock, with the counter at zero.
+    pub const fn new() -> Self {
+        ClkoutClock {
+            ticks: AtomicU32::new(0),
+        }
+    }
+
+    /// Set the clock output to the frequency of the clock and enable it, with a single write.
+    ///
+    /// Will return an 'Error::InvalidInputData' if `HZ` is not a clock output frequency.
+    pub fn start<I2C, E>(&self, rtc: &mut PCF8563<I2C>) -> Result<(), Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        let frequency = ClkoutFreq::from_hz(HZ).ok_or(Error::InvalidInputData)?;
+        rtc.set_clkout(frequency, Control::On)
+    }
+
+    /// Count one edge of the clock output, wrapping around.
+    ///
+    /// Must be called from a single interrupt handler: the counter is only loaded and
+    /// stored, which is also supported by the targets without atomic read-modify-write.
+    pub fn tick(&self) {
+        let ticks = self.ticks.load(Ordering::Relaxed);
+        self.ticks.store(ticks.wrapping_add(1), Ordering::Relaxed);
+    }
+}
+
+impl<const HZ: u32> Clock for ClkoutClock<HZ> {
+    type T = u32;
+
+    const SCALING_FACTOR: Fraction = Fraction::new(1, HZ);
+
+    fn try_now(&self) -> Result<Instant<Self>, clock::Error> {
+        Ok(Instant::new(self.ticks.load(Ordering::Relaxed)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Register;
+    use core::convert::TryFrom;
+    use embedded_hal_mock::I2cMock;
+    use embedded_time::duration::Milliseconds;
+
+    #[test]
+    fn counts_ticks() {
+        let clock = ClkoutClock::<32>::new();
+        let start = clock.try_now().unwrap();
+        for _ in 0..16 {
+            clock.tick();
+        }
+        let elapsed = clock
+            .try_now()
+            .unwrap()
+            .checked_duration_since(&start)
+            .unwrap();
+        assert_eq!(
+            Milliseconds::<u32>::try_from(elapsed).unwrap(),
+            Milliseconds(500)
+        );
+    }
+
+    #[test]
+    fn can_start_clock_output() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        ClkoutClock::<32>::new().start(&mut rtc).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CLKOUT_CTRL, 0x82]);
+        let mut rtc = PCF8563::new(i2c);
+        assert!(ClkoutClock::<1000>::new().start(&mut rtc).is_err());
+    }
+}
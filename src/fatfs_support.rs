@@ -0,0 +1,129 @@
+//! Time provider for the [`fatfs`](https://crates.io/crates/fatfs) crate (requires the `fatfs` feature).
+//!
+//! Files created or modified through `fatfs` get their timestamps from the RTC,
+//! e.g. on a Raspberry Pi exporting a USB drive without network time.
+
+use super::{hal, DateTime, PCF8563};
+use fatfs::{Date, TimeProvider};
+use hal::blocking::i2c::{Write, WriteRead};
+use std::fmt;
+use std::sync::Mutex;
+
+/// `fatfs::TimeProvider` reading the date and time from the RTC.
+///
+/// If the RTC can't be read, the DOS minimal date and time (1980-01-01 00:00:00) is returned,
+/// as `fatfs` does when no clock is available.
+pub struct RtcTimeProvider<I2C> {
+    rtc: Mutex<PCF8563<I2C>>,
+}
+
+impl<I2C, E> RtcTimeProvider<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Create a new time provider from the RTC driver.
+    pub fn new(rtc: PCF8563<I2C>) -> Self {
+        RtcTimeProvider {
+            rtc: Mutex::new(rtc),
+        }
+    }
+
+    /// Destroy the time provider, return the RTC driver.
+    pub fn destroy(self) -> PCF8563<I2C> {
+        match self.rtc.into_inner() {
+            Ok(rtc) => rtc,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Read the date and time and the full year, `None` if the RTC can't be read.
+    fn datetime(&self) -> Option<(DateTime, u16)> {
+        let mut rtc = self.rtc.lock().ok()?;
+        let datetime = rtc.get_datetime().ok()?;
+        Some((datetime, rtc.get_year_mapping().full_year(datetime.year)?))
+    }
+}
+
+impl<I2C> fmt::Debug for RtcTimeProvider<I2C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RtcTimeProvider").finish()
+    }
+}
+
+/// Convert the RTC date to the DOS date used by `fatfs`.
+fn to_fat_date(datetime: &DateTime, year: u16) -> Date {
+    Date {
+        year,
+        month: u16::from(datetime.month),
+        day: u16::from(datetime.day),
+    }
+}
+
+/// DOS minimal date and time (1980-01-01 00:00:00).
+fn fat_epoch() -> fatfs::DateTime {
+    fatfs::DateTime {
+        date: Date {
+            year: 1980,
+            month: 1,
+            day: 1,
+        },
+        time: fatfs::Time {
+            hour: 0,
+            min: 0,
+            sec: 0,
+            millis: 0,
+        },
+    }
+}
+
+impl<I2C, E> TimeProvider for RtcTimeProvider<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    fn get_current_date(&self) -> Date {
+        match self.datetime() {
+            Some((datetime, year)) => to_fat_date(&datetime, year),
+            None => fat_epoch().date,
+        }
+    }
+
+    fn get_current_date_time(&self) -> fatfs::DateTime {
+        match self.datetime() {
+            Some((datetime, year)) => fatfs::DateTime {
+                date: to_fat_date(&datetime, year),
+                time: fatfs::Time {
+                    hour: u16::from(datetime.hours),
+                    min: u16::from(datetime.minutes),
+                    sec: u16::from(datetime.seconds),
+                    millis: 0,
+                },
+            },
+            None => fat_epoch(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::I2cMock;
+
+    #[test]
+    fn provides_rtc_datetime() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x42, 0x15, 0x07, 0x04, 0x00, 0x04, 0x21]);
+        let provider = RtcTimeProvider::new(PCF8563::new(i2c));
+        let datetime = provider.get_current_date_time();
+        assert_eq!(
+            datetime.date,
+            Date {
+                year: 2021,
+                month: 4,
+                day: 4
+            }
+        );
+        assert_eq!(datetime.time.hour, 7);
+        assert_eq!(datetime.time.min, 15);
+        assert_eq!(datetime.time.sec, 42);
+    }
+}
@@ -0,0 +1,109 @@
+//! Timer and alarm functions taking `fugit` durations (requires the `fugit` feature).
+//!
+//! Any `fugit::Duration<u32, NOM, DENOM>` is accepted, e.g. `MillisDurationU32` or `SecsDurationU32`,
+//! and the actual duration is returned in the same unit, rounded to the nearest tick.
+//!
+//! `ClkoutFreq::hertz()` and `TimerFreq::hertz()` return the frequencies as `fugit` rates.
+
+use super::{hal, ClkoutFreq, DateTime, Error, TimerFreq, PCF8563};
+use fugit::{Duration as FugitDuration, Rate};
+use hal::blocking::i2c::{Write, WriteRead};
+
+/// Convert to `core::time::Duration`.
+fn to_core<const NOM: u32, const DENOM: u32>(
+    duration: FugitDuration<u32, NOM, DENOM>,
+) -> core::time::Duration {
+    let nanos = u128::from(duration.ticks()) * u128::from(NOM) * 1_000_000_000 / u128::from(DENOM);
+    core::time::Duration::from_nanos(nanos as u64)
+}
+
+/// Convert from `core::time::Duration`, rounding to the nearest tick.
+fn from_core<const NOM: u32, const DENOM: u32>(
+    duration: core::time::Duration,
+) -> FugitDuration<u32, NOM, DENOM> {
+    let period = u128::from(NOM) * 1_000_000_000;
+    let ticks = (duration.as_nanos() * u128::from(DENOM) + period / 2) / period;
+    FugitDuration::<u32, NOM, DENOM>::from_ticks(ticks.min(u128::from(u32::MAX)) as u32)
+}
+
+/// Convert a number of ticks in a number of seconds to a rate, rounding to the nearest unit.
+fn to_rate<const NOM: u32, const DENOM: u32>(ticks: u128, seconds: u128) -> Rate<u32, NOM, DENOM> {
+    let unit = seconds * u128::from(NOM);
+    let raw = (ticks * u128::from(DENOM) + unit / 2) / unit;
+    Rate::<u32, NOM, DENOM>::from_raw(raw.min(u128::from(u32::MAX)) as u32)
+}
+
+impl ClkoutFreq {
+    /// Clock output frequency as a `fugit` rate, e.g. `HertzU32`.
+    pub fn hertz<const NOM: u32, const DENOM: u32>(self) -> Rate<u32, NOM, DENOM> {
+        to_rate(u128::from(self.hz()), 1)
+    }
+}
+
+impl TimerFreq {
+    /// Timer frequency as a `fugit` rate, rounded to the nearest unit, e.g. `HertzU32`.
+    ///
+    /// 1/60 Hz is exact with `Rate<u32, 1, 60>` and rounds to 0 with `HertzU32`.
+    pub fn hertz<const NOM: u32, const DENOM: u32>(self) -> Rate<u32, NOM, DENOM> {
+        let (ticks, seconds) = self.rate();
+        to_rate(ticks, seconds)
+    }
+}
+
+impl<I2C, E> PCF8563<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Same as `set_timer_duration()`, with a `fugit` duration.
+    pub fn set_timer_fugit_duration<const NOM: u32, const DENOM: u32>(
+        &mut self,
+        duration: FugitDuration<u32, NOM, DENOM>,
+    ) -> Result<FugitDuration<u32, NOM, DENOM>, Error<E>> {
+        self.set_timer_duration(to_core(duration)).map(from_core)
+    }
+
+    /// Same as `alarm_in()`, with a `fugit` duration.
+    pub fn alarm_in_fugit<const NOM: u32, const DENOM: u32>(
+        &mut self,
+        duration: FugitDuration<u32, NOM, DENOM>,
+    ) -> Result<DateTime, Error<E>> {
+        self.alarm_in(to_core(duration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::I2cMock;
+    use fugit::{ExtU32, HertzU32, KilohertzU32, MillisDurationU32};
+
+    #[test]
+    fn can_convert_frequencies() {
+        let hertz: HertzU32 = ClkoutFreq::Clkout_32768Hz.hertz();
+        assert_eq!(hertz.raw(), 32_768);
+        let kilohertz: KilohertzU32 = ClkoutFreq::Clkout_1024Hz.hertz();
+        assert_eq!(kilohertz.raw(), 1);
+        let hertz: HertzU32 = TimerFreq::Timer_64Hz.hertz();
+        assert_eq!(hertz.raw(), 64);
+        let per_minute: Rate<u32, 1, 60> = TimerFreq::Timer_1_60Hz.hertz();
+        assert_eq!(per_minute.raw(), 1);
+    }
+
+    #[test]
+    fn can_convert_durations() {
+        assert_eq!(to_core(1_500.millis::<1, 1000>()).as_millis(), 1_500);
+        let millis: MillisDurationU32 = from_core(core::time::Duration::from_micros(2_600));
+        assert_eq!(millis.ticks(), 3);
+    }
+
+    #[test]
+    fn can_set_timer_with_fugit_duration() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        let actual = rtc
+            .set_timer_fugit_duration(50.millis::<1, 1000>())
+            .unwrap();
+        assert_eq!(actual.ticks(), 50);
+        let actual = rtc.set_timer_fugit_duration(5.minutes::<1, 1>()).unwrap();
+        assert_eq!(actual.to_secs(), 300);
+    }
+}
@@ -0,0 +1,172 @@
+//! Self-check of the RTC, for production tests and field diagnostics.
+
+use super::{hal, AlarmSettings, BitFlags, DateTime, Error, Register, PCF8563};
+use hal::blocking::i2c::{Write, WriteRead};
+
+/// Result of `health_check()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HealthReport {
+    /// Date and time read.
+    pub datetime: DateTime,
+    /// Is the voltage low flag (VL) set, i.e. the time may be wrong?
+    pub voltage_low: bool,
+    /// Is the clock stopped (STOP)?
+    pub clock_stopped: bool,
+    /// Is any test mode (TEST1 or TESTC) enabled?
+    pub test_mode: bool,
+    /// Are all the date and time components within their ranges?
+    pub datetime_valid: bool,
+    /// Is the alarm interrupt enabled with no alarm component enabled?
+    pub alarm_interrupt_unused: bool,
+    /// Is the timer interrupt enabled with the timer stopped?
+    pub timer_interrupt_unused: bool,
+}
+
+impl HealthReport {
+    /// Check that no problem was found.
+    pub fn is_healthy(&self) -> bool {
+        !self.voltage_low
+            && !self.clock_stopped
+            && !self.test_mode
+            && self.datetime_valid
+            && !self.alarm_interrupt_unused
+            && !self.timer_interrupt_unused
+    }
+}
+
+impl<I2C, E> PCF8563<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Check whether the date and time can be trusted: voltage low flag cleared, clock running
+    /// and date and time valid, read in one transaction.
+    pub fn is_time_valid(&mut self) -> Result<bool, Error<E>> {
+        let mut data = [0; 9];
+        self.read_registers(Register::CTRL_STATUS_1, &mut data)?;
+        let mut time = [0; 7];
+        time.copy_from_slice(&data[2..]);
+        Ok(data[0] & BitFlags::STOP == 0
+            && data[2] & BitFlags::VL == 0
+            && DateTime::from_registers(&time).is_valid())
+    }
+
+    /// Recover the RTC on power-up if the date and time can't be trusted, see `is_time_valid()`.
+    ///
+    /// In that case, the RTC is initialized with `rtc_init()`, the default date and time
+    /// are written and the clock is started, which also clears the voltage low flag.
+    /// Returns whether the recovery was needed.
+    ///
+    /// Will return an 'Error::InvalidInputData' if the default date and time is out of range.
+    pub fn power_up_recovery(&mut self, default_datetime: &DateTime) -> Result<bool, Error<E>> {
+        if self.is_time_valid()? {
+            return Ok(false);
+        }
+        if !default_datetime.is_valid() {
+            return Err(Error::InvalidInputData);
+        }
+        self.rtc_init()?;
+        self.set_datetime_and_start(default_datetime)?;
+        Ok(true)
+    }
+
+    /// Read all the registers in one transaction and check them.
+    pub fn health_check(&mut self) -> Result<HealthReport, Error<E>> {
+        let mut data = [0; 16];
+        self.read_registers(Register::CTRL_STATUS_1, &mut data)?;
+        let mut time = [0; 7];
+        time.copy_from_slice(&data[2..9]);
+        let datetime = DateTime::from_registers(&time);
+        let mut alarm = [0; 4];
+        alarm.copy_from_slice(&data[9..13]);
+        let alarm = AlarmSettings::from_registers(alarm);
+        let alarm_enabled = alarm.minutes.enabled
+            || alarm.hours.enabled
+            || alarm.day.enabled
+            || alarm.weekday.enabled;
+        Ok(HealthReport {
+            datetime,
+            voltage_low: data[2] & BitFlags::VL != 0,
+            clock_stopped: data[0] & BitFlags::STOP != 0,
+            test_mode: data[0] & (BitFlags::TEST1 | BitFlags::TESTC) != 0,
+            datetime_valid: datetime.is_valid(),
+            alarm_interrupt_unused: data[1] & BitFlags::AIE != 0 && !alarm_enabled,
+            timer_interrupt_unused: data[1] & BitFlags::TIE != 0 && data[14] & BitFlags::TE == 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::I2cMock;
+
+    const DEFAULT: DateTime = DateTime {
+        year: 25,
+        month: 1,
+        weekday: 3,
+        day: 1,
+        hours: 0,
+        minutes: 0,
+        seconds: 0,
+    };
+
+    #[test]
+    fn time_is_invalid_if_clock_stopped() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x20, 0x00, 0x30, 0x15, 0x09, 0x04, 0x03, 0x06, 0x25]);
+        let mut rtc = PCF8563::new(i2c);
+        assert!(!rtc.is_time_valid().unwrap());
+    }
+
+    #[test]
+    fn power_up_recovery_keeps_valid_time() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x00, 0x00, 0x30, 0x15, 0x09, 0x04, 0x03, 0x06, 0x25]);
+        let mut rtc = PCF8563::new(i2c);
+        assert!(!rtc.power_up_recovery(&DEFAULT).unwrap());
+    }
+
+    #[test]
+    fn power_up_recovery_sets_default_if_vl_set() {
+        let mut i2c = I2cMock::new();
+        // seconds with VL set, then the reads of `rtc_init()` and `control_clock()`
+        i2c.set_read_data(&[
+            0x00, 0x00, 0xb0, 0x15, 0x09, 0x04, 0x03, 0x06, 0x25, 0x80, 0x80, 0x80, 0x80, 0x80,
+            0x00,
+        ]);
+        let mut rtc = PCF8563::new(i2c);
+        assert!(rtc.power_up_recovery(&DEFAULT).unwrap());
+    }
+
+    #[test]
+    fn healthy_rtc_passes_check() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[
+            0x00, 0x02, 0x30, 0x15, 0x09, 0x04, 0x03, 0x06, 0x25, 0x00, 0x07, 0x80, 0x80, 0x00,
+            0x03, 0x00,
+        ]);
+        let mut rtc = PCF8563::new(i2c);
+        let report = rtc.health_check().unwrap();
+        assert!(report.is_healthy());
+        assert_eq!(report.datetime.minutes, 15);
+    }
+
+    #[test]
+    fn check_reports_problems() {
+        let mut i2c = I2cMock::new();
+        // STOP and TEST1 set, TIE set with the timer stopped, VL set, February 30th
+        i2c.set_read_data(&[
+            0xa0, 0x01, 0x80, 0x00, 0x00, 0x30, 0x00, 0x02, 0x25, 0x80, 0x80, 0x80, 0x80, 0x00,
+            0x03, 0x00,
+        ]);
+        let mut rtc = PCF8563::new(i2c);
+        let report = rtc.health_check().unwrap();
+        assert!(report.voltage_low);
+        assert!(report.clock_stopped);
+        assert!(report.test_mode);
+        assert!(!report.datetime_valid);
+        assert!(!report.alarm_interrupt_unused);
+        assert!(report.timer_interrupt_unused);
+        assert!(!report.is_healthy());
+    }
+}
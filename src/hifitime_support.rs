@@ -0,0 +1,102 @@
+//! Conversions between `DateTime` and `hifitime::Epoch` (requires the `hifitime` feature).
+//!
+//! The RTC is assumed to keep UTC. The `TryFrom` conversions map the two-digit year of the RTC
+//! to the years 2000-2099, `to_hifitime()` and `from_hifitime()` use the given year mapping,
+//! e.g. for dates before 2000.
+
+use super::{DateTime, OutOfRangeError, YearMapping};
+use core::convert::TryFrom;
+use hifitime::Epoch;
+
+impl DateTime {
+    /// Convert to a UTC `hifitime::Epoch`, mapping the year as given.
+    /// The weekday is ignored.
+    pub fn to_hifitime(&self, mapping: YearMapping) -> Result<Epoch, OutOfRangeError> {
+        Epoch::maybe_from_gregorian_utc(
+            i32::from(mapping.full_year(self.year).ok_or(OutOfRangeError)?),
+            self.month,
+            self.day,
+            self.hours,
+            self.minutes,
+            self.seconds,
+            0,
+        )
+        .map_err(|_| OutOfRangeError)
+    }
+
+    /// Create from a `hifitime::Epoch` in UTC, mapping the year as given.
+    /// The weekday is set counting from Sunday (0), sub-second precision is dropped.
+    pub fn from_hifitime(epoch: Epoch, mapping: YearMapping) -> Result<Self, OutOfRangeError> {
+        let (year, month, day, hours, minutes, seconds, _) = epoch.to_gregorian_utc();
+        Ok(DateTime {
+            year: mapping.two_digit_year(year).ok_or(OutOfRangeError)?,
+            month,
+            // hifitime counts from Monday (0)
+            weekday: (u8::from(epoch.weekday_utc()) + 1) % 7,
+            day,
+            hours,
+            minutes,
+            // a leap second is kept at 59
+            seconds: seconds.min(59),
+        })
+    }
+}
+
+impl TryFrom<DateTime> for Epoch {
+    type Error = OutOfRangeError;
+
+    /// Convert the RTC date and time to UTC, the weekday is ignored.
+    fn try_from(datetime: DateTime) -> Result<Self, Self::Error> {
+        datetime.to_hifitime(YearMapping::default())
+    }
+}
+
+impl TryFrom<Epoch> for DateTime {
+    type Error = OutOfRangeError;
+
+    /// Convert to the RTC date and time in UTC, the weekday is set counting from Sunday (0).
+    /// Sub-second precision is dropped.
+    fn try_from(epoch: Epoch) -> Result<Self, Self::Error> {
+        DateTime::from_hifitime(epoch, YearMapping::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_convert_to_and_from_hifitime() {
+        let epoch = Epoch::from_gregorian_utc(2021, 4, 4, 7, 15, 0, 0);
+        let datetime = DateTime::try_from(epoch).unwrap();
+        assert_eq!(
+            datetime,
+            DateTime {
+                year: 21,
+                month: 4,
+                weekday: 0,
+                day: 4,
+                hours: 7,
+                minutes: 15,
+                seconds: 0,
+            }
+        );
+        assert_eq!(Epoch::try_from(datetime), Ok(epoch));
+    }
+
+    #[test]
+    fn can_convert_with_year_mapping() {
+        let mapping = YearMapping::new(2000, 70).unwrap();
+        let epoch = Epoch::from_gregorian_utc(1999, 12, 31, 23, 59, 59, 0);
+        let datetime = DateTime::from_hifitime(epoch, mapping).unwrap();
+        assert_eq!(datetime.year, 99);
+        assert_eq!(datetime.weekday, 5);
+        assert_eq!(datetime.to_hifitime(mapping), Ok(epoch));
+    }
+
+    #[test]
+    fn cannot_convert_years_outside_of_range() {
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2100, 1, 1);
+        assert_eq!(DateTime::try_from(epoch), Err(OutOfRangeError));
+    }
+}
@@ -0,0 +1,126 @@
+//! Driver bundled with the input pin connected to the open drain INT output, which is active low.
+//!
+//! The blocking functions take an `embedded-hal` `InputPin`, `wait()` takes an
+//! `embedded-hal-async` `Wait` input (requires the `async` feature).
+//!
+//! ```rust,ignore
+//! let mut rtc = RtcWithIrq::new(PCF8563::new(i2c), int_pin);
+//! loop {
+//!     match rtc.service()? {
+//!         InterruptSource::None => { /* other work */ }
+//!         source => { /* alarm and/or timer */ }
+//!     }
+//! }
+//! ```
+
+use super::{hal, Error, InterruptSource, PCF8563};
+#[cfg(feature = "async")]
+use embedded_hal_async::digital::Wait;
+use hal::blocking::i2c::{Write, WriteRead};
+use hal::digital::v2::InputPin;
+
+/// Driver owning the input pin connected to the interrupt output.
+#[derive(Debug)]
+pub struct RtcWithIrq<I2C, PIN> {
+    rtc: PCF8563<I2C>,
+    pin: PIN,
+}
+
+impl<I2C, PIN> RtcWithIrq<I2C, PIN> {
+    /// Bundle the driver with the input pin connected to the interrupt output.
+    pub fn new(rtc: PCF8563<I2C>, pin: PIN) -> Self {
+        RtcWithIrq { rtc, pin }
+    }
+
+    /// Access the driver.
+    pub fn rtc(&mut self) -> &mut PCF8563<I2C> {
+        &mut self.rtc
+    }
+
+    /// Destroy the wrapper, return the driver and the pin.
+    pub fn destroy(self) -> (PCF8563<I2C>, PIN) {
+        (self.rtc, self.pin)
+    }
+}
+
+impl<I2C, E, PIN> RtcWithIrq<I2C, PIN>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+    PIN: InputPin,
+{
+    /// Is the interrupt output active (low)?
+    ///
+    /// Will return an `Error::Pin` if the pin can't be read.
+    pub fn irq_asserted(&self) -> Result<bool, Error<E>> {
+        self.pin.is_low().map_err(|_| Error::Pin)
+    }
+
+    /// If the interrupt output is active, find out which flags are set and clear them
+    /// with `handle_interrupt()`, otherwise nothing is read from the RTC.
+    ///
+    /// Will return an `Error::Pin` if the pin can't be read.
+    pub fn service(&mut self) -> Result<InterruptSource, Error<E>> {
+        if self.irq_asserted()? {
+            self.rtc.handle_interrupt()
+        } else {
+            Ok(InterruptSource::None)
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I2C, E, PIN> RtcWithIrq<I2C, PIN>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+    PIN: Wait,
+{
+    /// Wait until the interrupt output is active, then find out which flags are set
+    /// and clear them, see `next_event()`.
+    pub async fn wait(&mut self) -> Result<InterruptSource, Error<E>> {
+        self.rtc
+            .next_event(&mut self.pin)
+            .await
+            .map(InterruptSource::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Register;
+    use core::convert::Infallible;
+    use embedded_hal_mock::I2cMock;
+
+    /// Interrupt pin with a fixed level.
+    struct Level(bool);
+
+    impl InputPin for Level {
+        type Error = Infallible;
+
+        fn is_high(&self) -> Result<bool, Infallible> {
+            Ok(self.0)
+        }
+
+        fn is_low(&self) -> Result<bool, Infallible> {
+            Ok(!self.0)
+        }
+    }
+
+    #[test]
+    fn service_reads_nothing_if_not_asserted() {
+        let mut rtc = RtcWithIrq::new(PCF8563::new(I2cMock::new()), Level(true));
+        assert_eq!(rtc.service().unwrap(), InterruptSource::None);
+    }
+
+    #[test]
+    fn service_clears_flags_if_asserted() {
+        let mut i2c = I2cMock::new();
+        // AF and AIE set
+        i2c.set_read_data(&[0x0a]);
+        let mut rtc = RtcWithIrq::new(PCF8563::new(i2c), Level(false));
+        assert_eq!(rtc.service().unwrap(), InterruptSource::Alarm);
+        let (rtc, _) = rtc.destroy();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x06]);
+    }
+}
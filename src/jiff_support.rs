@@ -0,0 +1,119 @@
+//! Conversions between `DateTime` and `jiff::civil::DateTime` (requires the `jiff` feature).
+//!
+//! The `TryFrom` conversions map the two-digit year of the RTC to the years 2000-2099,
+//! `to_jiff()` and `from_jiff()` use the given year mapping.
+
+use super::{DateTime, OutOfRangeError, YearMapping};
+use core::convert::TryFrom;
+use jiff::civil;
+
+impl DateTime {
+    /// Convert to `jiff::civil::DateTime`, mapping the year as given.
+    /// The weekday is ignored and computed by `jiff`.
+    pub fn to_jiff(&self, mapping: YearMapping) -> Result<civil::DateTime, OutOfRangeError> {
+        civil::DateTime::new(
+            i16::try_from(mapping.full_year(self.year).ok_or(OutOfRangeError)?)
+                .map_err(|_| OutOfRangeError)?,
+            self.month as i8,
+            self.day as i8,
+            self.hours as i8,
+            self.minutes as i8,
+            self.seconds as i8,
+            0,
+        )
+        .map_err(|_| OutOfRangeError)
+    }
+
+    /// Create from `jiff::civil::DateTime`, mapping the year as given.
+    /// The weekday is set counting from Sunday (0), sub-second precision is dropped.
+    pub fn from_jiff(
+        datetime: civil::DateTime,
+        mapping: YearMapping,
+    ) -> Result<Self, OutOfRangeError> {
+        Ok(DateTime {
+            year: mapping
+                .two_digit_year(i32::from(datetime.year()))
+                .ok_or(OutOfRangeError)?,
+            month: datetime.month() as u8,
+            weekday: datetime.weekday().to_sunday_zero_offset() as u8,
+            day: datetime.day() as u8,
+            hours: datetime.hour() as u8,
+            minutes: datetime.minute() as u8,
+            seconds: datetime.second() as u8,
+        })
+    }
+}
+
+impl TryFrom<DateTime> for civil::DateTime {
+    type Error = OutOfRangeError;
+
+    /// Convert the RTC date and time, the weekday is ignored and computed by `jiff`.
+    fn try_from(datetime: DateTime) -> Result<Self, Self::Error> {
+        datetime.to_jiff(YearMapping::default())
+    }
+}
+
+impl TryFrom<civil::DateTime> for DateTime {
+    type Error = OutOfRangeError;
+
+    /// Convert to the RTC date and time, the weekday is set counting from Sunday (0).
+    /// Sub-second precision is dropped.
+    fn try_from(datetime: civil::DateTime) -> Result<Self, Self::Error> {
+        DateTime::from_jiff(datetime, YearMapping::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_convert_to_and_from_jiff() {
+        let jiff_datetime = civil::date(2021, 4, 4).at(7, 15, 0, 0);
+        let datetime = DateTime::try_from(jiff_datetime).unwrap();
+        assert_eq!(
+            datetime,
+            DateTime {
+                year: 21,
+                month: 4,
+                weekday: 0,
+                day: 4,
+                hours: 7,
+                minutes: 15,
+                seconds: 0,
+            }
+        );
+        assert_eq!(civil::DateTime::try_from(datetime), Ok(jiff_datetime));
+    }
+
+    #[test]
+    fn can_convert_with_year_mapping() {
+        let mapping = YearMapping::new(2000, 70).unwrap();
+        let jiff_datetime = civil::date(1999, 12, 31).at(23, 59, 59, 0);
+        let datetime = DateTime::from_jiff(jiff_datetime, mapping).unwrap();
+        assert_eq!(datetime.year, 99);
+        assert_eq!(datetime.weekday, 5);
+        assert_eq!(datetime.to_jiff(mapping), Ok(jiff_datetime));
+    }
+
+    #[test]
+    fn cannot_convert_years_beyond_i16() {
+        let mapping = YearMapping::new(u16::MAX - 99, 100).unwrap();
+        let datetime = DateTime {
+            year: 99,
+            month: 1,
+            weekday: 0,
+            day: 1,
+            hours: 0,
+            minutes: 0,
+            seconds: 0,
+        };
+        assert_eq!(datetime.to_jiff(mapping), Err(OutOfRangeError));
+    }
+
+    #[test]
+    fn cannot_convert_years_outside_of_range() {
+        let jiff_datetime = civil::date(2100, 1, 1).at(0, 0, 0, 0);
+        assert_eq!(DateTime::try_from(jiff_datetime), Err(OutOfRangeError));
+    }
+}
@@ -37,14 +37,18 @@
 //!
 //! ```rust
 //! use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
 //!
-//! let mut rtc = PCF::new(i2c);
+//! let mut rtc = PCF8563::new(i2c);
 //! ```
 //!
 //! The RTC doesn't need any special setup, you can just start reading from/ writing to it.
 //! The wrapper function `rtc_init()` can be used for initialization of the device:
 //!
 //! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let mut rtc = PCF8563::new(i2c);
 //! rtc.rtc_init().unwrap();
 //! ```
 //!
@@ -52,6 +56,19 @@
 //! alarms, timer and special modes (power-on-reset override, external clock).
 //! It also sets the timer to the lowest possible frequency (1/60 Hz) for power saving.
 //!
+//! The clock output is left as it is, i.e. enabled at 32768 Hz after power-on. `rtc_init_with()`
+//! can also disable it, e.g. when the pin is not used, or set its frequency:
+//!
+//! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let mut rtc = PCF8563::new(i2c);
+//! rtc.rtc_init_with(&InitConfig {
+//!     clkout: ClkoutInit::Disable,
+//! })
+//! .unwrap();
+//! ```
+//!
 //!
 //! ### Date and time
 //!
@@ -59,10 +76,17 @@
 //!
 //! - `set_datetime` (sets all the date and time components at once)
 //! - `get_datetime` (reads all the date and time components at once)
+//! - `get_datetime_consistent` (reads the date and time until two consecutive reads match)
 //! - `set_time` (sets only time components, all at once)
+//! - `set_datetime_with_vl` (same as `set_datetime`, but the voltage low flag can be preserved
+//!   or checked instead of being cleared)
+//! - `set_datetime_and_start` (sets all the date and time components and starts the clock)
+//! - `start_clock_at` (stops the clock, sets all the date and time components and starts the clock
+//!   when a closure returns, e.g. on a GPS PPS edge)
 //!  
 //! ```rust
-//!
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
 //! let mut rtc = PCF8563::new(i2c);
 //!
 //! let now = DateTime {
@@ -78,8 +102,129 @@
 //! rtc.set_datetime(&now).unwrap();
 //! ```
 //!
+//! With the `serde` feature enabled, `DateTime` and `Time` can be serialized, see the
+//! `serialization` module for the RFC 3339 and compact binary formats. The alarm setting
+//! can be serialized too, e.g. to provision devices with the output of `export_alarm()`
+//! passed to `import_alarm()`.
+//!
+//! With the `names` feature enabled, `weekday_name()` and `month_name()` (and their short
+//! three-letter versions) return the English names for displaying the date:
+//!
+//! ```rust,ignore
+//! let now = rtc.get_datetime().unwrap();
+//! println!("{}, {} {}", weekday_name(now.weekday), now.day, month_name(now.month));
+//! ```
+//!
 //! __TO DO__: add description of the century flag
 //!
+//! With the `jiff` feature enabled, `DateTime` can be converted to and from `jiff::civil::DateTime`
+//! (years 2000-2099, or any year mapping with `to_jiff()` and `from_jiff()`):
+//!
+//! ```rust,ignore
+//! let now = jiff::civil::DateTime::try_from(rtc.get_datetime().unwrap()).unwrap();
+//! let later = now + jiff::Span::new().hours(1);
+//! rtc.set_datetime(&DateTime::try_from(later).unwrap()).unwrap();
+//! ```
+//!
+//! With the `hifitime` feature enabled, `DateTime` (in UTC) can be converted to and from
+//! `hifitime::Epoch` the same way, with `to_hifitime()` and `from_hifitime()` for other year mappings.
+//!
+//! With the `rtcc` feature enabled, the driver implements the `DateTimeAccess` and `Rtcc` traits
+//! of the [`rtcc`](https://crates.io/crates/rtcc) crate, so it can be used by generic code
+//! written for any real-time clock.
+//!
+//! The RTC can be kept in UTC, while the application reads and writes the local time
+//! through the `LocalClock` wrapper with a fixed offset from UTC:
+//!
+//! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let rtc = PCF8563::new(i2c);
+//! let mut clock = LocalClock::new(rtc, 120); // UTC+02:00
+//! let local = clock.get_datetime();
+//!
+//! // Central European Time, following the daylight saving time rules of the European Union
+//! clock.set_offset_minutes(60);
+//! clock.set_dst_rules(Some(DstRules::EU));
+//! ```
+//!
+//! The time zone can also be given as a POSIX TZ string, without a time zone database:
+//!
+//! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let rtc = PCF8563::new(i2c);
+//! let tz: PosixTz = "CET-1CEST,M3.5.0,M10.5.0/3".parse().unwrap();
+//! let mut clock = LocalClock::with_tz(rtc, &tz);
+//! ```
+//!
+//! With the `chrono-tz` feature enabled (requires `std`), `get_local_datetime()` and
+//! `set_local_datetime()` convert to and from any time zone of the tz database instead,
+//! e.g. `chrono_tz::Europe::Berlin`.
+//!
+//! `DateTime` can be converted to and from a Unix timestamp with `to_unix_timestamp()` and
+//! `from_unix_timestamp()`, or the driver can read and set the Unix timestamp directly with
+//! `get_unix_timestamp()` and `set_unix_timestamp()`.
+//! For SNTP clients, `set_ntp_seconds()` and `get_ntp_seconds()` take care of the
+//! 1900-based NTP epoch and of the era rollover in 2036.
+//!
+//! The RTC stores a two-digit year, by default taken as 2000-2099. The driver can map it differently
+//! with `set_year_mapping()`, which applies to all the conversions done by the driver:
+//!
+//! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let mut rtc = PCF8563::new(i2c);
+//! // years 00-69 are 2000-2069, years 70-99 are 1970-1999
+//! rtc.set_year_mapping(YearMapping::new(2000, 70).unwrap());
+//! ```
+//!
+//! With the `std` feature enabled, `DateTime` can also be converted to and from
+//! `std::time::SystemTime`, and the RTC can be compared with or set from the system clock,
+//! similarly to `hwclock`:
+//!
+//! ```rust,ignore
+//! if rtc.system_clock_offset().unwrap().abs() > 1 {
+//!     rtc.sync_from_system_clock().unwrap();
+//! }
+//! ```
+//!
+//! With the `cli` feature enabled, the crate also builds a `pcf8563` binary for Linux boards,
+//! which can read, set and dump the RTC, and synchronize it with the system clock:
+//!
+//! ```text
+//! pcf8563 --bus /dev/i2c-1 sync-to-sys
+//! ```
+//!
+//! With the `commands` feature enabled, `serve_command()` reads a text command such as `TIME?`,
+//! `TIME=2025-06-04T12:00:00` or `ALARM=06:30` from a serial port implementing the
+//! [`embedded-io`](https://crates.io/crates/embedded-io) traits, executes it and sends the response.
+//!
+//! With the `defmt-timestamp` feature enabled, `DefmtTimestamp` gives the `defmt` log messages
+//! wall-clock timestamps, computed from the uptime and the RTC time read at boot.
+//!
+//! With the `rtic` feature enabled, `RtcMonotonic` implements the RTIC `Monotonic` trait
+//! with a resolution of one second, scheduling the tasks with the timer and the alarm.
+//!
+//! With the `nmea` feature enabled, the RTC can be set from the `RMC` or `ZDA` sentences
+//! sent by a GPS receiver, e.g. with every line received until the first one succeeds:
+//!
+//! ```rust,ignore
+//! if rtc.sync_from_nmea(line).is_ok() {
+//!     synced = true;
+//! }
+//! ```
+//!
+//! With the `fatfs` feature enabled (requires `std`), `RtcTimeProvider` implements `fatfs::TimeProvider`,
+//! so that files written with the [`fatfs`](https://crates.io/crates/fatfs) crate get their timestamps
+//! from the RTC:
+//!
+//! ```rust,ignore
+//! let time_provider = Box::leak(Box::new(RtcTimeProvider::new(rtc)));
+//! let options = fatfs::FsOptions::new().time_provider(time_provider);
+//! let fs = fatfs::FileSystem::new(disk, options)?;
+//! ```
+//!
 //! ### Alarm
 //!
 //! All the alarm-related functions are defined in the `alarm.rs` module:
@@ -89,6 +234,9 @@
 //! - enabling and disabling alarm interrupt (interrupt pin set to active when the alarm event occurs)
 //!
 //! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let mut rtc = PCF8563::new(i2c);
 //! // set the alarm to 9:25, the alarm flag AF will be set at that time,
 //! // and the interrupt pin set to active
 //! rtc.set_alarm_minutes(25).unwrap();
@@ -98,18 +246,137 @@
 //! rtc.control_alarm_interrupt(Control::On).unwrap();
 //!```
 //!
+//! The same alarm can be set in a single transaction with the alarm builder,
+//! the components which are not given are disabled:
+//!
+//! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let mut rtc = PCF8563::new(i2c);
+//! rtc.alarm().minutes(25).hours(9).interrupt(true).apply().unwrap();
+//! ```
+//!
+//! The components can also be controlled through `AlarmComponent`, e.g. to iterate over them:
+//!
+//! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let mut rtc = PCF8563::new(i2c);
+//! for component in AlarmComponent::ALL.iter() {
+//!     rtc.control_alarm(*component, Control::Off).unwrap();
+//! }
+//! ```
+//!
 //! To check the alarm flag and clear after it's set:
 //!
 //! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let mut rtc = PCF8563::new(i2c);
 //! if rtc.get_alarm_flag().unwrap() {
 //!     rtc.clear_alarm_flag().unwrap()
 //! }
 //!```
 //!
+//! The whole alarm can also be set or read at once, with all the registers written or read
+//! in a single transaction. Components set to `None` are disabled:
+//!
+//! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let mut rtc = PCF8563::new(i2c);
+//! rtc.set_alarm(&AlarmConfig {
+//!     minutes: Some(25),
+//!     hours: Some(9),
+//!     day: None,
+//!     weekday: None,
+//! })
+//! .unwrap();
+//! let alarm = rtc.get_alarm().unwrap();
+//!
+//! // the same alarm every day at 9:25, with a single call
+//! rtc.set_alarm_time(9, 25).unwrap();
+//!
+//! // every month on the 1st at 6:00
+//! rtc.set_monthly_alarm(1, 6, 0, MonthDayPolicy::Strict).unwrap();
+//! ```
+//!
+//! A relative alarm can be set with `alarm_in()`, which also enables the alarm interrupt:
+//!
+//! ```rust,ignore
+//! // wake up in 90 minutes
+//! rtc.alarm_in(core::time::Duration::from_secs(90 * 60)).unwrap();
+//! ```
+//!
+//! With the `chrono` feature enabled, an `AlarmConfig` can be created from a `chrono::NaiveTime`
+//! (daily alarm) or from a `NaiveTime` and a `chrono::Weekday` (weekly alarm):
+//!
+//! ```rust,ignore
+//! let time = chrono::NaiveTime::from_hms_opt(7, 30, 0).unwrap();
+//! rtc.set_alarm(&AlarmConfig::from((time, chrono::Weekday::Mon))).unwrap();
+//! ```
+//!
+//! An alarm repeating at a fixed interval is handled by `RecurringAlarm`,
+//! which sets the next alarm each time it is serviced after the alarm flag is set:
+//!
+//! ```rust,ignore
+//! let mut alarm = RecurringAlarm::new(core::time::Duration::from_secs(6 * 3600)).unwrap();
+//! alarm.start(&mut rtc).unwrap();
+//! // in the interrupt handler or main loop
+//! alarm.service(&mut rtc).unwrap();
+//! ```
+//!
+//! Similarly, `WeekdaySchedule` sets the alarm to a time on the selected weekdays only:
+//!
+//! ```rust,ignore
+//! let schedule = WeekdaySchedule::new(WeekdaySchedule::WORKDAYS, 7, 0).unwrap();
+//! schedule.start(&mut rtc).unwrap();
+//! ```
+//!
+//! Several events can share the single alarm with `AlarmScheduler`, which always sets
+//! the alarm to the earliest pending event:
+//!
+//! ```rust,ignore
+//! let mut scheduler = AlarmScheduler::<4>::new();
+//! let slot = scheduler.add(&datetime).unwrap();
+//! scheduler.arm(&mut rtc).unwrap();
+//! // when the alarm interrupt occurs
+//! for slot in scheduler.service(&mut rtc).unwrap() {
+//!     // handle the event in the slot
+//! }
+//! ```
+//!
+//! Schedules can also be given as a restricted cron expression (`minute hour day-of-month day-of-week`),
+//! e.g. received over a serial port:
+//!
+//! ```rust,ignore
+//! let schedule: CronSchedule = "*/15 8-17 * 1-5".parse().unwrap();
+//! schedule.start(&mut rtc).unwrap();
+//! // after the alarm flag is set
+//! if let Some(next) = schedule.service(&mut rtc).unwrap() {
+//!     // the schedule was due, the alarm is set to the next occurrence
+//! }
+//! ```
+//!
+//! Single components can be set and enabled, or disabled, with one call using `Option`:
+//!
+//! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let mut rtc = PCF8563::new(i2c);
+//! rtc.set_alarm_minutes_opt(Some(25)).unwrap(); // set to 25 and enable
+//! rtc.set_alarm_weekday_opt(None).unwrap(); // disable
+//! ```
+//!
+//! `get_alarm_settings()` also reads the values of the disabled components, e.g. for displaying them.
+//!
 //! Each alarm component has to be enabled separately: minutes, hours, day, weekday,
 //! but a wrapper function was defined to disable all the alarms at once:
 //!
 //! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let mut rtc = PCF8563::new(i2c);
 //! rtc.disable_all_alarms().unwrap();
 //! ```
 //!
@@ -130,6 +397,9 @@
 //! the result of an OR operation, i.e. will be active when either alarm or timer will trigger the interrupt event.
 //!
 //! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let mut rtc = PCF8563::new(i2c);
 //! rtc.set_timer_frequency(TimerFreq::Timer_1Hz).unwrap(); // set frequency to 1 Hz
 //! rtc.set_timer(30).unwrap(); // set timer to 30 ticks
 //! rtc.control_timer_interrupt(Control::On).unwrap(); // enable timer interrupt
@@ -141,6 +411,103 @@
 //! rtc.clear_timer_flag().unwrap(); // clear the timer flag
 //! ```
 //!
+//! Instead of choosing the frequency and the number of ticks, the timer can be set for a duration,
+//! the finest frequency which can hold it is used (up to 255 minutes):
+//!
+//! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let mut rtc = PCF8563::new(i2c);
+//! // 205 ticks at 4096 Hz, i.e. 50.048 ms
+//! let actual = rtc.set_timer_duration(core::time::Duration::from_millis(50)).unwrap();
+//! ```
+//!
+//! With the `fugit` feature enabled, `set_timer_fugit_duration()` and `alarm_in_fugit()`
+//! take `fugit` durations instead, e.g. `50.millis()`.
+//! With the `embassy` feature enabled, `set_timer_embassy_duration()` and `alarm_in_embassy()`
+//! take `embassy_time` durations, and `wait_for_timer_embassy()` polls the timer flag asynchronously.
+//!
+//! With the `async` feature enabled, `wait_for_timer_interrupt()` awaits the interrupt pin
+//! (an `embedded-hal-async` `Wait` input), then confirms and clears the timer flag:
+//!
+//! ```rust,ignore
+//! rtc.start_periodic(TimerFreq::Timer_1Hz, 10).unwrap();
+//! rtc.control_timer_interrupt(Control::On).unwrap();
+//! loop {
+//!     rtc.wait_for_timer_interrupt(&mut int_pin).await.unwrap();
+//!     // every 10 seconds
+//! }
+//! ```
+//!
+//! `TimerTicker` wraps the same loop, with `ticker.next_tick().await` for each period.
+//!
+//! `next_event()` awaits the interrupt pin for either interrupt, clears the flags which were set
+//! and returns the `Event`:
+//!
+//! ```rust,ignore
+//! loop {
+//!     match rtc.next_event(&mut int_pin).await.unwrap() {
+//!         Event::Alarm => { /* alarm */ }
+//!         Event::Timer => { /* timer */ }
+//!         Event::Both => { /* alarm and timer */ }
+//!     }
+//! }
+//! ```
+//!
+//! With the `countdown` feature enabled, `RtcCountDown` implements the `embedded-hal`
+//! `CountDown`, `Periodic` and `Cancel` traits on top of the timer, so that drivers
+//! expecting a countdown timer can run off the RTC.
+//!
+//! `RtcWatchdog` turns the timer into a low-power external watchdog: the interrupt pin goes low,
+//! e.g. resetting the microcontroller, unless the watchdog is fed before the timeout:
+//!
+//! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let mut rtc = PCF8563::new(i2c);
+//! let watchdog = RtcWatchdog::new(TimerFreq::Timer_1Hz, 30).unwrap();
+//! watchdog.start(&mut rtc).unwrap();
+//! loop {
+//!     // work for less than 30 seconds
+//!     watchdog.feed(&mut rtc).unwrap();
+//! #   break;
+//! }
+//! ```
+//!
+//! Superloop firmware can poll the timer flag with `poll_timer()`, which clears it once set:
+//!
+//! ```rust,ignore
+//! match rtc.poll_timer() {
+//!     Ok(()) => { /* timer expired */ }
+//!     Err(nb::Error::WouldBlock) => { /* other work */ }
+//!     Err(nb::Error::Other(e)) => { /* I2C error */ }
+//! }
+//! ```
+//!
+//! When both interrupts are enabled, the handler of the interrupt pin can use `handle_interrupt()`
+//! to find out which one triggered it, clearing the flags:
+//!
+//! ```rust,ignore
+//! match rtc.handle_interrupt().unwrap() {
+//!     InterruptSource::Alarm => { /* alarm */ }
+//!     InterruptSource::Timer => { /* timer */ }
+//!     InterruptSource::Both => { /* alarm and timer */ }
+//!     InterruptSource::None => {}
+//! }
+//! ```
+//!
+//! `service()` does the same, calling a closure for each flag which was set:
+//!
+//! ```rust,ignore
+//! rtc.service(|| { /* alarm */ }, || { /* timer */ }).unwrap();
+//! ```
+//!
+//! `RtcWithIrq` bundles the driver with the input pin connected to the interrupt output, only reading
+//! the flags when the pin is active with `service()`, or awaiting it with `wait()` (feature `async`).
+//!
+//! Both interrupts and the timer interrupt output mode can be set at once with `set_interrupt_config()`,
+//! without risking to clear a flag, and read back with `get_interrupt_config()`.
+//!
 //! ### Clock output
 //!
 //! All the clock output-related functions are defined in the `clkout.rs` module
@@ -158,10 +525,19 @@
 //! On reset the clock output is enabled and set to 32768 Hz
 //!
 //! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let mut rtc = PCF8563::new(i2c);
 //! rtc.set_clkout_frequency(ClkoutFreq::Clkout_1024Hz).unwrap(); // set the frequency
 //! rtc.control_clkout(Control::On).unwrap(); // enable the clock output
 //! ```
 //!
+//! `CrystalCalibrator` measures the error of the RTC crystal, in ppm, from the clock output edges
+//! captured by a timer of the microcontroller.
+//!
+//! With the `embedded-time` feature enabled, `ClkoutClock` implements the `embedded_time::Clock`
+//! trait by counting the clock output edges in an interrupt handler.
+//!
 //! ### RTC Control
 //! All the other control functions are defined in the `control.rs` module
 //!
@@ -169,12 +545,39 @@
 //! - `is_clock_running()` - checks the STOP flag (if cleared, the clock is running)
 //! - `get_voltage_low_flag()` - checks whether the VL flag was triggered (see datasheet for details)
 //! - `clear_voltage_low_flag()` - clears the voltage low detection flag
+//! - `clear_all_flags()` - clears the alarm, timer and voltage low flags
+//! - `set_voltage_low_policy()` - chooses what `get_datetime()` and `check_power()` do when the voltage
+//!   low flag is set: ignore it, return an error or reset the date and time to a default
+//! - `is_time_valid()` - checks that the voltage low flag is cleared, the clock is running and the date
+//!   and time are valid
+//! - `power_up_recovery()` - initializes the RTC and writes a default date and time if the voltage low
+//!   flag is set, the clock is stopped or the date and time are invalid
+//! - `health_check()` - reads all the registers at once and reports the voltage low flag, stopped clock,
+//!   enabled test modes, invalid date and time, and interrupts enabled without alarm or timer
 //! - `control_ext_clk_test_mode()` - enables the EXT_CLK test mode (see datasheet for details)
 //! - `control_power_on_reset_override()` - enables the POR override mode (see datasheet for details)
+//! - `clear_test_modes()` - disables both the EXT_CLK test mode and the POR override mode
+//!
+//! As enabling the EXT_CLK test mode stops the normal timekeeping, `control_ext_clk_test_mode()` and
+//! `control_power_on_reset_override()` require the `danger` feature. So do `enter_external_clock_mode()`
+//! and `exit_external_clock_mode()`, following the datasheet sequence for test fixtures driving
+//! the prescaler through the CLKOUT pin (64 edges per second).
+//! - `software_reset()` - resets all the registers to their power-on values, restarting the prescaler
+//! - `status()` - reads all the bits of both control registers at once
+//! - `snapshot()` - reads all the registers at once, `RegisterSnapshot::diff()` lists the registers
+//!   which changed between two snapshots, e.g. to debug what changed the configuration
+//! - `read_control_registers()` and `write_control_registers()` - read or write the raw values of both
+//!   control registers at once, e.g. to implement sequences from the datasheet
+//!
+//! The configuration of the clock output, timer and interrupts can be read and written as a whole
+//! with `get_config()` and `set_config()`, and `power_estimate()` gives the typical supply current
+//! of a configuration, e.g. for battery life calculations. `low_power_profile()` disables the clock
+//! output, the timer and both interrupts, for the lowest current. `suspend()` does the same, returning
+//! the configuration, which `resume()` restores.
 
 #![deny(unsafe_code)]
 #![deny(missing_docs)]
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 
 use embedded_hal as hal;
 
@@ -187,6 +590,18 @@ pub enum Error<E> {
     I2C(E),
     /// Invalid input data
     InvalidInputData,
+    /// Voltage low flag is set, the date and time may be invalid
+    VoltageLow,
+    /// Consecutive reads of the same registers returned different data
+    InconsistentRead,
+    /// Registers read back after a write don't hold the written data
+    VerificationFailed,
+    /// Interrupt pin error
+    Pin,
+    /// The timer must be stopped for this operation
+    TimerRunning,
+    /// The alarm flag is set with the alarm interrupt enabled, holding the interrupt pin low
+    AlarmPending,
 }
 
 struct Register;
@@ -247,16 +662,113 @@ pub enum Control {
 pub struct PCF8563<I2C> {
     /// The concrete I2C device implementation.
     i2c: I2C,
+    /// Mapping of the two-digit year to a full year.
+    year_mapping: YearMapping,
+    /// Behavior when the voltage low flag is found set.
+    voltage_low_policy: VoltageLowPolicy,
+    /// Read back the alarm registers after writing them.
+    verify_alarm_writes: bool,
+    /// Seconds to count with the timer after the alarm set by `set_precise_alarm()`.
+    precise_alarm_seconds: Option<u8>,
+    /// Handling of the timer by `service_timer()`.
+    timer_mode: TimerMode,
 }
 
 mod alarm;
+#[cfg(feature = "async")]
+mod async_support;
+mod calendar;
+mod calibration;
+#[cfg(feature = "chrono")]
+mod chrono_support;
+#[cfg(feature = "chrono-tz")]
+mod chrono_tz_support;
 mod clkout;
+#[cfg(feature = "commands")]
+mod command;
+mod config;
 mod control;
+#[cfg(feature = "countdown")]
+mod countdown;
+mod cron;
 mod datetime;
+#[cfg(feature = "defmt-timestamp")]
+mod defmt_timestamp;
+#[cfg(feature = "embassy")]
+mod embassy_support;
+#[cfg(feature = "embedded-time")]
+mod embedded_time_support;
+#[cfg(feature = "fatfs")]
+mod fatfs_support;
+#[cfg(feature = "fugit")]
+mod fugit_support;
+mod health;
+#[cfg(feature = "hifitime")]
+mod hifitime_support;
+mod irq;
+#[cfg(feature = "jiff")]
+mod jiff_support;
+mod local;
+#[cfg(feature = "names")]
+mod names;
+#[cfg(feature = "nmea")]
+mod nmea;
+mod power;
+#[cfg(feature = "rtcc")]
+mod rtcc_support;
+#[cfg(feature = "rtic")]
+mod rtic_support;
+mod schedule;
+#[cfg(feature = "serde")]
+pub mod serialization;
+mod snapshot;
+#[cfg(feature = "std")]
+mod std_support;
 mod timer;
-pub use clkout::ClkoutFreq;
-pub use datetime::{DateTime, Time};
-pub use timer::{InterruptOutput, TimerFreq};
+mod tz;
+mod watchdog;
+pub use alarm::{
+    AlarmBuilder, AlarmComponent, AlarmConfig, AlarmExport, AlarmSetting, AlarmSettings,
+    AlarmStatus, MonthDayPolicy,
+};
+#[cfg(feature = "async")]
+pub use async_support::{Event, TimerTicker};
+pub use calibration::CrystalCalibrator;
+pub use clkout::{ClkoutActive, ClkoutConfig, ClkoutFreq, ClkoutGuard};
+#[cfg(feature = "commands")]
+pub use command::{Command, CommandError, CommandParseError};
+pub use config::Config;
+pub use control::{ClkoutInit, InitConfig, InterruptConfig, InterruptSource, Status};
+#[cfg(feature = "countdown")]
+pub use countdown::RtcCountDown;
+pub use cron::{CronParseError, CronSchedule};
+pub use datetime::{
+    DateTime, OutOfRangeError, Time, VoltageLowHandling, VoltageLowPolicy, Weekday, YearMapping,
+};
+#[cfg(feature = "defmt-timestamp")]
+pub use defmt_timestamp::DefmtTimestamp;
+#[cfg(feature = "embedded-time")]
+pub use embedded_time_support::ClkoutClock;
+#[cfg(feature = "fatfs")]
+pub use fatfs_support::RtcTimeProvider;
+pub use health::HealthReport;
+pub use irq::RtcWithIrq;
+pub use local::{DstRules, LocalClock, Transition, TransitionClock};
+#[cfg(feature = "names")]
+pub use names::{month_name, month_name_short, weekday_name, weekday_name_short};
+#[cfg(feature = "nmea")]
+pub use nmea::NmeaParseError;
+pub use power::{power_estimate, MicroAmps};
+#[cfg(feature = "rtic")]
+pub use rtic_support::RtcMonotonic;
+pub use schedule::{AlarmScheduler, DueEvents, RecurringAlarm, WeekdaySchedule};
+pub use snapshot::{RegisterChange, RegisterDiff, RegisterSnapshot};
+pub use timer::{
+    InterruptOutput, LongTimer, Quantization, TimerConfig, TimerDispatcher, TimerFreq, TimerMode,
+    TimerState,
+};
+pub use tz::{PosixTz, TzParseError};
+pub use watchdog::RtcWatchdog;
 
 impl<I2C, E> PCF8563<I2C>
 where
@@ -264,7 +776,14 @@ where
 {
     /// Create a new instance of the PCF8563 driver.
     pub fn new(i2c: I2C) -> Self {
-        PCF8563 { i2c }
+        PCF8563 {
+            i2c,
+            year_mapping: YearMapping::default(),
+            voltage_low_policy: VoltageLowPolicy::Ignore,
+            verify_alarm_writes: false,
+            precise_alarm_seconds: None,
+            timer_mode: TimerMode::OneShot,
+        }
     }
 
     /// Destroy driver instance, return I2C bus instance.
@@ -287,6 +806,23 @@ where
             .and(Ok(data[0]))
     }
 
+    /// Write to consecutive registers in one transaction (up to 16 registers).
+    fn write_registers(&mut self, register: u8, data: &[u8]) -> Result<(), Error<E>> {
+        let mut payload = [0; 17];
+        payload[0] = register;
+        payload[1..=data.len()].copy_from_slice(data);
+        self.i2c
+            .write(DEVICE_ADDRESS, &payload[..=data.len()])
+            .map_err(Error::I2C)
+    }
+
+    /// Read from consecutive registers in one transaction.
+    fn read_registers(&mut self, register: u8, data: &mut [u8]) -> Result<(), Error<E>> {
+        self.i2c
+            .write_read(DEVICE_ADDRESS, &[register], data)
+            .map_err(Error::I2C)
+    }
+
     /// Check if specific bits are set.
     fn is_register_bit_flag_high(&mut self, address: u8, bitmask: u8) -> Result<bool, Error<E>> {
         let data = self.read_register(address)?;
@@ -297,7 +833,7 @@ where
     fn set_register_bit_flag(&mut self, address: u8, bitmask: u8) -> Result<(), Error<E>> {
         let data = self.read_register(address)?;
         if (data & bitmask) == 0 {
-            self.write_register(address, data | bitmask)
+            self.write_register(address, keep_interrupt_flags(address, data | bitmask))
         } else {
             Ok(())
         }
@@ -307,13 +843,25 @@ where
     fn clear_register_bit_flag(&mut self, address: u8, bitmask: u8) -> Result<(), Error<E>> {
         let data = self.read_register(address)?;
         if (data & bitmask) != 0 {
-            self.write_register(address, data & !bitmask)
+            self.write_register(address, keep_interrupt_flags(address, data) & !bitmask)
         } else {
             Ok(())
         }
     }
 }
 
+/// Set the alarm and timer flags in a value written to the control/status 2 register.
+///
+/// Writing 1 leaves these flags unchanged, while writing 0 clears them, so a flag set
+/// by the RTC between the read and the write of a read-modify-write is not lost.
+fn keep_interrupt_flags(address: u8, data: u8) -> u8 {
+    if address == Register::CTRL_STATUS_2 {
+        data | BitFlags::AF | BitFlags::TF
+    } else {
+        data
+    }
+}
+
 /// Convert the Binary Coded Decimal value to decimal (only the lowest 7 bits).
 fn decode_bcd(input: u8) -> u8 {
     let digits: u8 = input & 0xf;
@@ -360,4 +908,166 @@ mod tests {
         assert_eq!(0b0010_0001, encode_bcd(21));
         assert_eq!(0b0101_1001, encode_bcd(59));
     }
+
+    const DATETIME: DateTime = DateTime {
+        year: 21,
+        month: 4,
+        weekday: 0,
+        day: 4,
+        hours: 7,
+        minutes: 15,
+        seconds: 42,
+    };
+
+    #[test]
+    fn can_convert_unix_timestamp() {
+        assert_eq!(DATETIME.to_unix_timestamp(), Ok(1_617_520_542));
+        assert_eq!(DateTime::from_unix_timestamp(1_617_520_542), Ok(DATETIME));
+        assert_eq!(
+            DateTime::from_unix_timestamp(946_684_799),
+            Err(OutOfRangeError)
+        );
+    }
+
+    #[test]
+    fn can_convert_ntp_seconds() {
+        assert_eq!(DATETIME.to_ntp_seconds(), Ok(3_826_509_342));
+        assert_eq!(DateTime::from_ntp_seconds(3_826_509_342), Ok(DATETIME));
+        // 2040-01-01 00:00:00 is in NTP era 1
+        let datetime = DateTime {
+            year: 40,
+            month: 1,
+            weekday: 0,
+            day: 1,
+            hours: 0,
+            minutes: 0,
+            seconds: 0,
+        };
+        assert_eq!(datetime.to_ntp_seconds(), Ok(123_010_304));
+        assert_eq!(DateTime::from_ntp_seconds(123_010_304), Ok(datetime));
+    }
+
+    #[test]
+    fn can_map_years() {
+        let mapping = YearMapping::new(2000, 70).unwrap();
+        assert_eq!(mapping.full_year(0), Some(2000));
+        assert_eq!(mapping.full_year(69), Some(2069));
+        assert_eq!(mapping.full_year(70), Some(1970));
+        assert_eq!(mapping.full_year(100), None);
+        assert_eq!(mapping.two_digit_year(1999), Some(99));
+        assert_eq!(mapping.two_digit_year(2069), Some(69));
+        assert_eq!(mapping.two_digit_year(2070), None);
+        assert_eq!(mapping.two_digit_year(1969), None);
+        assert_eq!(YearMapping::Y2000.two_digit_year(2099), Some(99));
+        assert_eq!(YearMapping::Y2000.two_digit_year(2100), None);
+        assert!(YearMapping::new(2000, 101).is_err());
+        assert!(YearMapping::new(u16::MAX - 98, 100).is_err());
+        let mapping = YearMapping::new(u16::MAX - 99, 100).unwrap();
+        assert_eq!(mapping.full_year(99), Some(u16::MAX));
+        assert_eq!(mapping.full_year(255), None);
+    }
+
+    #[test]
+    fn validates_february_with_year_mapping() {
+        let leap_day = DateTime {
+            year: 0,
+            month: 2,
+            day: 29,
+            ..DATETIME
+        };
+        assert!(leap_day.is_valid());
+        assert!(leap_day.to_unix_timestamp().is_ok());
+        // 2100 is not a leap year
+        let mapping = YearMapping::new(2100, 100).unwrap();
+        assert!(!leap_day.is_valid_with(mapping));
+        assert_eq!(
+            leap_day.to_unix_timestamp_with(mapping),
+            Err(OutOfRangeError)
+        );
+    }
+
+    #[test]
+    fn get_datetime_consistent_retries_until_reads_match() {
+        let data = [
+            0x59, 0x59, 0x23, 0x31, 0x05, 0x12, 0x21, // 2021-12-31 23:59:59
+            0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x22, // 2022-01-01 00:00:00
+            0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x22,
+        ];
+        let mut i2c = hal::I2cMock::new();
+        i2c.set_read_data(&data);
+        let mut rtc = PCF8563::new(i2c);
+        let datetime = rtc.get_datetime_consistent().unwrap();
+        assert_eq!(datetime.year, 22);
+        assert_eq!(datetime.month, 1);
+        assert_eq!(datetime.seconds, 0);
+    }
+
+    #[test]
+    fn set_datetime_clears_vl() {
+        let mut i2c = hal::I2cMock::new();
+        i2c.set_read_data(&[0b1000_0000]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.set_datetime(&DATETIME).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data()[1], 0b0100_0010);
+    }
+
+    #[test]
+    fn set_datetime_can_preserve_vl() {
+        let mut i2c = hal::I2cMock::new();
+        i2c.set_read_data(&[0b1000_0000]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.set_datetime_with_vl(&DATETIME, VoltageLowHandling::Preserve)
+            .unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data()[1], 0b1100_0010);
+    }
+
+    #[test]
+    fn set_datetime_can_fail_if_vl_set() {
+        let mut i2c = hal::I2cMock::new();
+        i2c.set_read_data(&[0b1000_0000]);
+        let mut rtc = PCF8563::new(i2c);
+        match rtc.set_datetime_with_vl(&DATETIME, VoltageLowHandling::FailIfSet) {
+            Err(Error::VoltageLow) => (),
+            _ => panic!("expected Error::VoltageLow"),
+        }
+    }
+
+    #[test]
+    fn start_clock_at_starts_clock_after_wait() {
+        let mut i2c = hal::I2cMock::new();
+        // STOP and TESTC set
+        i2c.set_read_data(&[0x28]);
+        let mut rtc = PCF8563::new(i2c);
+        let mut waited = false;
+        rtc.start_clock_at(&DATETIME, || waited = true).unwrap();
+        assert!(waited);
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_1, 0x08]);
+    }
+
+    #[test]
+    fn get_datetime_can_report_vl() {
+        let mut i2c = hal::I2cMock::new();
+        i2c.set_read_data(&[0xb0, 0x15, 0x09, 0x04, 0x03, 0x06, 0x25]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.set_voltage_low_policy(VoltageLowPolicy::ReportError);
+        match rtc.get_datetime() {
+            Err(Error::VoltageLow) => (),
+            _ => panic!("expected Error::VoltageLow"),
+        }
+    }
+
+    #[test]
+    fn get_datetime_can_reset_to_default_if_vl_set() {
+        let mut i2c = hal::I2cMock::new();
+        // seconds with VL set
+        i2c.set_read_data(&[0xb0, 0x15, 0x09, 0x04, 0x03, 0x06, 0x25]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.set_voltage_low_policy(VoltageLowPolicy::ResetToDefault(DATETIME));
+        assert_eq!(rtc.get_datetime().unwrap(), DATETIME);
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data()[1], 0b0100_0010);
+    }
 }
@@ -0,0 +1,340 @@
+//! Local time presentation of a RTC holding the UTC time.
+//!
+//! Keeping the RTC in UTC and converting only when reading and writing through the
+//! `LocalClock` wrapper avoids mixing up UTC and local time in the application code.
+//!
+//! Daylight saving time can be applied automatically with `DstRules`, the rules
+//! used in the European Union and in the United States are provided.
+
+use super::{calendar, hal, DateTime, Error, PCF8563};
+use hal::blocking::i2c::{Write, WriteRead};
+
+/// Clock in which the time of a daylight saving time transition is given.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransitionClock {
+    /// UTC time.
+    Utc,
+    /// Local time in effect before the transition.
+    Local,
+}
+
+/// Daylight saving time transition, e.g. the last Sunday of March at 01:00 UTC.
+///
+/// Out of range fields are clamped to their ranges when computing the transition,
+/// except the weekday which is taken modulo 7.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    /// Month [1-12].
+    pub month: u8,
+    /// Week of the month [1-5], 5 meaning the last one.
+    pub week: u8,
+    /// Weekday [0-6] (0: Sunday).
+    pub weekday: u8,
+    /// Time of the transition in minutes after midnight.
+    pub minutes: i16,
+    /// Clock in which the time of the transition is given.
+    pub clock: TransitionClock,
+}
+
+impl Transition {
+    /// Timestamp of the transition in the year, the offsets are given in minutes.
+    fn timestamp(&self, year: i32, offset_minutes: i16) -> i64 {
+        let month = self.month.clamp(1, 12);
+        let first = calendar::days_from_civil(year, month, 1);
+        let mut day = 1 + (7 + self.weekday % 7 - calendar::weekday_from_days(first)) % 7;
+        day += 7 * (self.week.clamp(1, 5) - 1);
+        while day > calendar::days_in_month(year, month) {
+            day -= 7;
+        }
+        let offset = match self.clock {
+            TransitionClock::Utc => 0,
+            TransitionClock::Local => i64::from(offset_minutes),
+        };
+        calendar::days_from_civil(year, month, day) * calendar::SECONDS_PER_DAY
+            + (i64::from(self.minutes) - offset) * 60
+    }
+}
+
+/// Daylight saving time rules.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DstRules {
+    /// Start of the daylight saving time.
+    pub start: Transition,
+    /// End of the daylight saving time.
+    pub end: Transition,
+    /// Time added to the standard offset during the daylight saving time, in minutes.
+    pub save_minutes: i16,
+}
+
+impl DstRules {
+    /// Rules of the European Union: from the last Sunday of March
+    /// to the last Sunday of October, at 01:00 UTC.
+    pub const EU: DstRules = DstRules {
+        start: Transition {
+            month: 3,
+            week: 5,
+            weekday: 0,
+            minutes: 60,
+            clock: TransitionClock::Utc,
+        },
+        end: Transition {
+            month: 10,
+            week: 5,
+            weekday: 0,
+            minutes: 60,
+            clock: TransitionClock::Utc,
+        },
+        save_minutes: 60,
+    };
+
+    /// Rules of the United States: from the second Sunday of March
+    /// to the first Sunday of November, at 02:00 local time.
+    pub const US: DstRules = DstRules {
+        start: Transition {
+            month: 3,
+            week: 2,
+            weekday: 0,
+            minutes: 120,
+            clock: TransitionClock::Local,
+        },
+        end: Transition {
+            month: 11,
+            week: 1,
+            weekday: 0,
+            minutes: 120,
+            clock: TransitionClock::Local,
+        },
+        save_minutes: 60,
+    };
+
+    /// Check if the daylight saving time is in effect at the UTC timestamp,
+    /// for the standard offset from UTC given in minutes.
+    fn is_dst(&self, utc: i64, offset_minutes: i16) -> bool {
+        let local = utc + i64::from(offset_minutes) * 60;
+        let (year, _, _) = calendar::civil_from_days(local.div_euclid(calendar::SECONDS_PER_DAY));
+        let start = self.start.timestamp(year, offset_minutes);
+        let end = self.end.timestamp(year, offset_minutes + self.save_minutes);
+        if start < end {
+            utc >= start && utc < end
+        } else {
+            // southern hemisphere, the daylight saving time spans the new year
+            utc >= start || utc < end
+        }
+    }
+}
+
+/// Wrapper around the driver presenting the date and time with an offset from UTC,
+/// optionally following daylight saving time rules.
+#[derive(Debug)]
+pub struct LocalClock<I2C> {
+    rtc: PCF8563<I2C>,
+    offset_minutes: i16,
+    dst: Option<DstRules>,
+}
+
+impl<I2C, E> LocalClock<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Create a new local clock, the offset from UTC is given in minutes (e.g. 60 for UTC+01:00).
+    pub fn new(rtc: PCF8563<I2C>, offset_minutes: i16) -> Self {
+        LocalClock {
+            rtc,
+            offset_minutes,
+            dst: None,
+        }
+    }
+
+    /// Create a new local clock following the daylight saving time rules,
+    /// the standard offset from UTC is given in minutes.
+    pub fn with_dst(rtc: PCF8563<I2C>, offset_minutes: i16, rules: DstRules) -> Self {
+        LocalClock {
+            rtc,
+            offset_minutes,
+            dst: Some(rules),
+        }
+    }
+
+    /// Destroy the local clock, return the driver.
+    pub fn destroy(self) -> PCF8563<I2C> {
+        self.rtc
+    }
+
+    /// Access the driver, e.g. to set the alarm or the timer.
+    pub fn rtc(&mut self) -> &mut PCF8563<I2C> {
+        &mut self.rtc
+    }
+
+    /// Standard offset from UTC in minutes.
+    pub fn offset_minutes(&self) -> i16 {
+        self.offset_minutes
+    }
+
+    /// Change the standard offset from UTC, given in minutes.
+    pub fn set_offset_minutes(&mut self, offset_minutes: i16) {
+        self.offset_minutes = offset_minutes;
+    }
+
+    /// Change the daylight saving time rules (`None`: no daylight saving time).
+    pub fn set_dst_rules(&mut self, rules: Option<DstRules>) {
+        self.dst = rules;
+    }
+
+    /// Offset from UTC in effect at the UTC timestamp, in seconds.
+    fn offset_at(&self, utc: i64) -> i64 {
+        match self.dst {
+            Some(rules) if rules.is_dst(utc, self.offset_minutes) => {
+                i64::from(self.offset_minutes + rules.save_minutes) * 60
+            }
+            _ => i64::from(self.offset_minutes) * 60,
+        }
+    }
+
+    /// Check if the daylight saving time is currently in effect.
+    pub fn is_dst(&mut self) -> Result<bool, Error<E>> {
+        let utc = self.rtc.get_unix_timestamp()?;
+        Ok(self.offset_at(utc) != i64::from(self.offset_minutes) * 60)
+    }
+
+    /// Read the local date and time.
+    ///
+    /// Will return an 'Error::InvalidInputData' if the local date and time can't be represented
+    /// (year outside of the range of the year mapping of the driver).
+    pub fn get_datetime(&mut self) -> Result<DateTime, Error<E>> {
+        let utc = self.rtc.get_unix_timestamp()?;
+        let mapping = self.rtc.get_year_mapping();
+        Ok(DateTime::from_unix_timestamp_with(
+            utc + self.offset_at(utc),
+            mapping,
+        )?)
+    }
+
+    /// Set the local date and time, the RTC is set to the corresponding UTC date and time.
+    ///
+    /// A local time occurring twice when the daylight saving time ends is taken as daylight saving time.
+    ///
+    /// Will return an 'Error::InvalidInputData' if any of the parameters is out of range,
+    /// or the UTC date and time can't be represented.
+    pub fn set_datetime(&mut self, datetime: &DateTime) -> Result<(), Error<E>> {
+        let local = datetime.to_unix_timestamp_with(self.rtc.get_year_mapping())?;
+        let standard = i64::from(self.offset_minutes) * 60;
+        let utc = match self.dst {
+            Some(rules) => {
+                let daylight = standard + i64::from(rules.save_minutes) * 60;
+                if rules.is_dst(local - daylight, self.offset_minutes) {
+                    local - daylight
+                } else {
+                    local - standard
+                }
+            }
+            None => local - standard,
+        };
+        self.rtc.set_unix_timestamp(utc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::I2cMock;
+
+    #[test]
+    fn reads_local_time_across_midnight() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x00, 0x30, 0x23, 0x31, 0x05, 0x12, 0x21]);
+        let mut clock = LocalClock::new(PCF8563::new(i2c), 60);
+        let datetime = clock.get_datetime().unwrap();
+        assert_eq!(
+            datetime,
+            DateTime {
+                year: 22,
+                month: 1,
+                weekday: 6,
+                day: 1,
+                hours: 0,
+                minutes: 30,
+                seconds: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn finds_eu_transitions() {
+        // 2021-03-28 01:00 UTC and 2021-10-31 01:00 UTC
+        assert_eq!(DstRules::EU.start.timestamp(2021, 60), 1_616_893_200);
+        assert_eq!(DstRules::EU.end.timestamp(2021, 120), 1_635_642_000);
+        assert!(!DstRules::EU.is_dst(1_616_893_199, 60));
+        assert!(DstRules::EU.is_dst(1_616_893_200, 60));
+        assert!(DstRules::EU.is_dst(1_635_641_999, 60));
+        assert!(!DstRules::EU.is_dst(1_635_642_000, 60));
+    }
+
+    #[test]
+    fn clamps_out_of_range_transitions() {
+        let transition = Transition {
+            week: 200,
+            weekday: 7,
+            ..DstRules::EU.start
+        };
+        assert_eq!(
+            transition.timestamp(2021, 60),
+            DstRules::EU.start.timestamp(2021, 60)
+        );
+        let december = Transition {
+            month: 12,
+            ..DstRules::EU.start
+        };
+        let transition = Transition {
+            month: 255,
+            ..DstRules::EU.start
+        };
+        assert_eq!(transition.timestamp(2021, 60), december.timestamp(2021, 60));
+    }
+
+    #[test]
+    fn finds_us_transitions() {
+        // 2021-03-14 02:00 EST (07:00 UTC) and 2021-11-07 02:00 EDT (06:00 UTC)
+        assert_eq!(DstRules::US.start.timestamp(2021, -300), 1_615_705_200);
+        assert_eq!(DstRules::US.end.timestamp(2021, -240), 1_636_264_800);
+    }
+
+    #[test]
+    fn writes_utc_time() {
+        let mut clock = LocalClock::new(PCF8563::new(I2cMock::new()), -300);
+        let datetime = DateTime {
+            year: 21,
+            month: 4,
+            weekday: 0,
+            day: 4,
+            hours: 22,
+            minutes: 15,
+            seconds: 0,
+        };
+        clock.set_datetime(&datetime).unwrap();
+        let i2c = clock.destroy().destroy();
+        assert_eq!(
+            i2c.get_write_data(),
+            &[0x02, 0x00, 0x15, 0x03, 0x05, 0x01, 0x04, 0x21]
+        );
+    }
+
+    #[test]
+    fn writes_utc_time_during_dst() {
+        let mut clock = LocalClock::with_dst(PCF8563::new(I2cMock::new()), 60, DstRules::EU);
+        let datetime = DateTime {
+            year: 21,
+            month: 7,
+            weekday: 4,
+            day: 1,
+            hours: 12,
+            minutes: 0,
+            seconds: 0,
+        };
+        clock.set_datetime(&datetime).unwrap();
+        let i2c = clock.destroy().destroy();
+        assert_eq!(
+            i2c.get_write_data(),
+            &[0x02, 0x00, 0x00, 0x10, 0x01, 0x04, 0x07, 0x21]
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! English names of the weekdays and months (requires the `names` feature).
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Name of the weekday [0-6] (0: Sunday), "?" if out of range.
+pub fn weekday_name(weekday: u8) -> &'static str {
+    WEEKDAYS.get(usize::from(weekday)).unwrap_or(&"?")
+}
+
+/// Three-letter name of the weekday [0-6] (0: Sunday), "?" if out of range.
+pub fn weekday_name_short(weekday: u8) -> &'static str {
+    let name = weekday_name(weekday);
+    &name[..name.len().min(3)]
+}
+
+/// Name of the month [1-12], "?" if out of range.
+pub fn month_name(month: u8) -> &'static str {
+    match month {
+        1..=12 => MONTHS[usize::from(month) - 1],
+        _ => "?",
+    }
+}
+
+/// Three-letter name of the month [1-12], "?" if out of range.
+pub fn month_name_short(month: u8) -> &'static str {
+    let name = month_name(month);
+    &name[..name.len().min(3)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_name_weekdays_and_months() {
+        assert_eq!(weekday_name(0), "Sunday");
+        assert_eq!(weekday_name_short(3), "Wed");
+        assert_eq!(weekday_name(7), "?");
+        assert_eq!(month_name(1), "January");
+        assert_eq!(month_name_short(12), "Dec");
+        assert_eq!(month_name(0), "?");
+        assert_eq!(month_name_short(13), "?");
+    }
+}
@@ -0,0 +1,215 @@
+//! Setting the RTC from the time sent by a GPS receiver (requires the `nmea` feature).
+//!
+//! The date and time are taken from `RMC` sentences (`$GPRMC,hhmmss.ss,A,...,ddmmyy,...`)
+//! and `ZDA` sentences (`$GPZDA,hhmmss.ss,dd,mm,yyyy,...`), with any talker ID (e.g. `$GN`).
+//! The checksum is verified when present, the fractional seconds are dropped.
+//! `RMC` sentences with the status `V` (no fix) are rejected, as the receiver
+//! may not know the time yet.
+//!
+//! The time sent by the receiver is UTC, and refers to the beginning of the second
+//! in which the sentence is sent, so it is best used right after receiving it.
+
+use super::{hal, DateTime, Error, YearMapping, PCF8563};
+use core::convert::TryFrom;
+use hal::blocking::i2c::{Write, WriteRead};
+
+/// Error returned when an NMEA sentence can't be parsed or doesn't hold a valid date and time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NmeaParseError;
+
+impl DateTime {
+    /// Parse the date and time from an `RMC` or `ZDA` sentence, the year is taken as 2000-2099.
+    ///
+    /// The sentence may include the trailing `\r\n`.
+    pub fn from_nmea(sentence: &[u8]) -> Result<Self, NmeaParseError> {
+        DateTime::from_nmea_with(sentence, YearMapping::default())
+    }
+
+    /// Parse the date and time from an `RMC` or `ZDA` sentence, mapping the year as given.
+    ///
+    /// The weekday is set counting from Sunday (0).
+    pub fn from_nmea_with(sentence: &[u8], mapping: YearMapping) -> Result<Self, NmeaParseError> {
+        let body = checked_body(sentence)?;
+        let mut fields = body.split(|&b| b == b',');
+        let address = fields.next().ok_or(NmeaParseError)?;
+        if address.len() != 5 {
+            return Err(NmeaParseError);
+        }
+        let time = fields.next().ok_or(NmeaParseError)?;
+        let (year, month, day) = match &address[2..] {
+            b"RMC" => {
+                if fields.next() != Some(b"A") {
+                    return Err(NmeaParseError);
+                }
+                // latitude, longitude, speed and course
+                let date = fields.nth(6).ok_or(NmeaParseError)?;
+                if date.len() != 6 {
+                    return Err(NmeaParseError);
+                }
+                (
+                    mapping
+                        .full_year(parse_u8(&date[4..6])?)
+                        .ok_or(NmeaParseError)?,
+                    parse_u8(&date[2..4])?,
+                    parse_u8(&date[0..2])?,
+                )
+            }
+            b"ZDA" => {
+                let mut next = || fields.next().ok_or(NmeaParseError);
+                let day = parse_u8(next()?)?;
+                let month = parse_u8(next()?)?;
+                (parse_number(next()?)?, month, day)
+            }
+            _ => return Err(NmeaParseError),
+        };
+        if time.len() < 6 {
+            return Err(NmeaParseError);
+        }
+        let datetime = DateTime {
+            year: mapping
+                .two_digit_year(i32::from(year))
+                .ok_or(NmeaParseError)?,
+            month,
+            weekday: 0,
+            day,
+            hours: parse_u8(&time[0..2])?,
+            minutes: parse_u8(&time[2..4])?,
+            seconds: parse_u8(&time[4..6])?,
+        };
+        let timestamp = datetime
+            .to_unix_timestamp_with(mapping)
+            .map_err(|_| NmeaParseError)?;
+        DateTime::from_unix_timestamp_with(timestamp, mapping).map_err(|_| NmeaParseError)
+    }
+}
+
+impl<I2C, E> PCF8563<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Set the date and time from an `RMC` or `ZDA` sentence, according to the year mapping.
+    ///
+    /// Returns the date and time which was set.
+    /// Will return an 'Error::InvalidInputData' if the sentence can't be parsed,
+    /// so that it can be called with every sentence received from the GPS receiver.
+    pub fn sync_from_nmea(&mut self, sentence: &[u8]) -> Result<DateTime, Error<E>> {
+        let datetime = DateTime::from_nmea_with(sentence, self.year_mapping)
+            .map_err(|_| Error::InvalidInputData)?;
+        self.set_datetime(&datetime)?;
+        Ok(datetime)
+    }
+}
+
+/// The part of the sentence between `$` and `*`, after verifying the checksum if present.
+fn checked_body(sentence: &[u8]) -> Result<&[u8], NmeaParseError> {
+    let sentence = match sentence {
+        [b'$', rest @ ..] => rest,
+        _ => return Err(NmeaParseError),
+    };
+    let end = sentence
+        .iter()
+        .position(|&b| b == b'\r' || b == b'\n')
+        .unwrap_or(sentence.len());
+    let sentence = &sentence[..end];
+    match sentence.iter().position(|&b| b == b'*') {
+        Some(star) => {
+            let (body, checksum) = (&sentence[..star], &sentence[star + 1..]);
+            if checksum.len() != 2 {
+                return Err(NmeaParseError);
+            }
+            let expected = hex_digit(checksum[0])? << 4 | hex_digit(checksum[1])?;
+            if body.iter().fold(0, |sum, &b| sum ^ b) != expected {
+                return Err(NmeaParseError);
+            }
+            Ok(body)
+        }
+        None => Ok(sentence),
+    }
+}
+
+fn hex_digit(digit: u8) -> Result<u8, NmeaParseError> {
+    match digit {
+        b'0'..=b'9' => Ok(digit - b'0'),
+        b'A'..=b'F' => Ok(digit - b'A' + 10),
+        b'a'..=b'f' => Ok(digit - b'a' + 10),
+        _ => Err(NmeaParseError),
+    }
+}
+
+/// Parse a decimal number of at most four digits.
+fn parse_number(digits: &[u8]) -> Result<u16, NmeaParseError> {
+    if digits.is_empty() || digits.len() > 4 {
+        return Err(NmeaParseError);
+    }
+    digits.iter().try_fold(0, |value, &digit| match digit {
+        b'0'..=b'9' => Ok(value * 10 + u16::from(digit - b'0')),
+        _ => Err(NmeaParseError),
+    })
+}
+
+fn parse_u8(digits: &[u8]) -> Result<u8, NmeaParseError> {
+    u8::try_from(parse_number(digits)?).map_err(|_| NmeaParseError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::I2cMock;
+
+    const DATETIME: DateTime = DateTime {
+        year: 21,
+        month: 4,
+        weekday: 0,
+        day: 4,
+        hours: 7,
+        minutes: 15,
+        seconds: 42,
+    };
+
+    #[test]
+    fn can_parse_rmc_and_zda() {
+        assert_eq!(
+            DateTime::from_nmea(
+                b"$GPRMC,071542.00,A,4807.038,N,01131.000,E,022.4,084.4,040421,003.1,W*40\r\n"
+            ),
+            Ok(DATETIME)
+        );
+        assert_eq!(
+            DateTime::from_nmea(b"$GNZDA,071542.50,04,04,2021,00,00*79"),
+            Ok(DATETIME)
+        );
+        assert_eq!(
+            DateTime::from_nmea(b"$GNZDA,071542.50,04,04,2021,00,00"),
+            Ok(DATETIME)
+        );
+    }
+
+    #[test]
+    fn cannot_parse_invalid_sentences() {
+        for sentence in [
+            &b"$GPRMC,071542.00,V,,,,,,,040421,,,N*7B"[..],
+            b"$GNZDA,071542.50,04,04,2021,00,00*78",
+            b"$GPGGA,071542.00,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,",
+            b"$GPZDA,201530.00,04,07,1999,00,00",
+            b"$GPZDA,201530.00,31,04,2021,00,00",
+            b"GPZDA,201530.00,04,07,2002,00,00",
+            b"$GPZDA,2015,04,07,2002,00,00",
+        ] {
+            assert_eq!(DateTime::from_nmea(sentence), Err(NmeaParseError));
+        }
+    }
+
+    #[test]
+    fn can_sync_from_nmea() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        let datetime = rtc
+            .sync_from_nmea(b"$GNZDA,071542.50,04,04,2021,00,00*79")
+            .unwrap();
+        assert_eq!(datetime, DATETIME);
+        let i2c = rtc.destroy();
+        assert_eq!(
+            i2c.get_write_data(),
+            &[0x02, 0x42, 0x15, 0x07, 0x04, 0x00, 0x04, 0x21]
+        );
+    }
+}
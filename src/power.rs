@@ -0,0 +1,140 @@
+//! Estimation of the supply current drawn by the RTC.
+//!
+//! The estimate starts from the typical supply current given by the datasheet at VDD = 3.0 V
+//! and 25 °C with the I2C bus idle: 0.25 µA with the clock output disabled, 0.55 µA with the
+//! clock output enabled at 32768 Hz and no load. The clock output current scales with the
+//! frequency, the timer running at 4096 Hz adds a small switching current. The currents drawn
+//! by the load of the clock output and by the pull-up resistor of the interrupt pin are not included.
+//!
+//! `low_power_profile()` sets the configuration with the lowest current, `suspend()` and `resume()`
+//! do the same reversibly.
+
+use super::{hal, ClkoutFreq, Config, Error, Register, TimerFreq, TimerMode, PCF8563};
+use hal::blocking::i2c::{Write, WriteRead};
+
+/// Supply current in microamperes.
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Default)]
+pub struct MicroAmps(pub f32);
+
+/// Typical supply current with the clock output disabled.
+const BASE_CURRENT: f32 = 0.25;
+/// Additional current with the clock output enabled at 32768 Hz.
+const CLKOUT_CURRENT: f32 = 0.30;
+/// Additional current with the timer running at 4096 Hz.
+const TIMER_CURRENT: f32 = 0.02;
+
+/// Estimate the typical supply current of the RTC with the configuration.
+pub fn power_estimate(config: &Config) -> MicroAmps {
+    let mut current = BASE_CURRENT;
+    if config.clkout.enabled {
+        current += CLKOUT_CURRENT * config.clkout.frequency.hz() as f32
+            / ClkoutFreq::Clkout_32768Hz.hz() as f32;
+    }
+    if config.timer.enabled {
+        let (ticks, seconds) = config.timer.frequency.rate();
+        let (max_ticks, _) = TimerFreq::Timer_4096Hz.rate();
+        current += TIMER_CURRENT * ticks as f32 / (seconds * max_ticks) as f32;
+    }
+    MicroAmps(current)
+}
+
+impl<I2C, E> PCF8563<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Set the minimum current configuration in two transactions: both interrupts disabled
+    /// and both flags cleared, then the clock output disabled and the timer stopped at 1/60 Hz.
+    ///
+    /// Unlike `rtc_init()`, the clock output is disabled. The alarm registers, the timer value
+    /// and the first control register are not changed.
+    pub fn low_power_profile(&mut self) -> Result<(), Error<E>> {
+        self.write_register(Register::CTRL_STATUS_2, 0)?;
+        self.write_registers(
+            Register::CLKOUT_CTRL,
+            &[
+                ClkoutFreq::Clkout_32768Hz.bits(),
+                TimerFreq::Timer_1_60Hz.bits(),
+            ],
+        )?;
+        self.timer_mode = TimerMode::OneShot;
+        Ok(())
+    }
+
+    /// Read the configuration, then set the minimum current configuration with `low_power_profile()`,
+    /// e.g. before entering deep sleep or shipping mode. Returns the configuration for `resume()`.
+    ///
+    /// The timekeeping and the alarm registers are not affected, but the alarm interrupt is disabled.
+    /// The alarm and timer flags are cleared and not part of the configuration, so a pending
+    /// interrupt is lost: handle it before suspending.
+    pub fn suspend(&mut self) -> Result<Config, Error<E>> {
+        let config = self.get_config()?;
+        self.low_power_profile()?;
+        Ok(config)
+    }
+
+    /// Restore the configuration returned by `suspend()` with `set_config()`.
+    ///
+    /// The timer value is read as the remaining count when suspending, so a timer which was
+    /// running restarts from the value it had when suspended, and then also reloads that value
+    /// instead of the original one. Set the timer value again for a periodic timer.
+    pub fn resume(&mut self, config: &Config) -> Result<(), Error<E>> {
+        self.set_config(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClkoutConfig, TimerConfig};
+    use embedded_hal_mock::I2cMock;
+
+    fn config(clkout: bool, timer: bool) -> Config {
+        Config {
+            clkout: ClkoutConfig {
+                enabled: clkout,
+                frequency: ClkoutFreq::Clkout_32768Hz,
+            },
+            timer: TimerConfig {
+                enabled: timer,
+                frequency: TimerFreq::Timer_4096Hz,
+                ticks: 0,
+                interrupt: false,
+                pulse_mode: false,
+            },
+            alarm_interrupt: false,
+        }
+    }
+
+    #[test]
+    fn can_estimate_current() {
+        assert_eq!(power_estimate(&config(false, false)), MicroAmps(0.25));
+        assert!((power_estimate(&config(true, false)).0 - 0.55).abs() < 1e-6);
+        assert!(power_estimate(&config(true, true)) > power_estimate(&config(true, false)));
+    }
+
+    #[test]
+    fn can_set_low_power_profile() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        rtc.low_power_profile().unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CLKOUT_CTRL, 0x00, 0x03]);
+    }
+
+    #[test]
+    fn can_suspend_and_resume() {
+        let mut i2c = I2cMock::new();
+        // AIE set, clock output enabled at 1 Hz, timer running at 1 Hz from 10
+        i2c.set_read_data(&[0x02, 0x83, 0x82, 0x0a]);
+        let mut rtc = PCF8563::new(i2c);
+        let config = rtc.suspend().unwrap();
+        assert!(config.alarm_interrupt);
+        assert!(config.clkout.enabled);
+        assert!(config.timer.enabled);
+        rtc.resume(&config).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(
+            i2c.get_write_data(),
+            &[Register::CLKOUT_CTRL, 0x83, 0x82, 0x0a]
+        );
+    }
+}
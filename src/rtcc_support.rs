@@ -0,0 +1,219 @@
+//! Implementation of the [`rtcc`](https://crates.io/crates/rtcc) traits (requires the `rtcc` feature).
+//!
+//! The two-digit year of the RTC is mapped according to the year mapping of the driver, and the weekday
+//! is numbered [1-7] starting from Sunday, as expected by the traits.
+//!
+//! As the individual setters of the `Rtcc` trait have to keep the other components
+//! unchanged, they read all the date and time components, modify one and write them back.
+//! Prefer the `DateTimeAccess` methods whenever possible.
+
+use super::{hal, DateTime, Error, Time, VoltageLowHandling, YearMapping, PCF8563};
+use hal::blocking::i2c::{Write, WriteRead};
+use rtcc::{DateTimeAccess, Datelike, Hours, NaiveDate, NaiveDateTime, NaiveTime, Rtcc, Timelike};
+
+impl<I2C, E> PCF8563<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Read all the date and time components, change some of them and write them back.
+    fn update_datetime<F>(&mut self, update: F) -> Result<(), Error<E>>
+    where
+        F: FnOnce(&mut DateTime),
+    {
+        let mut datetime = self.get_datetime()?;
+        update(&mut datetime);
+        self.set_datetime_with_vl(&datetime, VoltageLowHandling::Preserve)
+    }
+}
+
+/// Convert the RTC date to `NaiveDate`.
+fn to_naive_date<E>(datetime: &DateTime, mapping: YearMapping) -> Result<NaiveDate, Error<E>> {
+    NaiveDate::from_ymd_opt(
+        i32::from(
+            mapping
+                .full_year(datetime.year)
+                .ok_or(Error::InvalidInputData)?,
+        ),
+        u32::from(datetime.month),
+        u32::from(datetime.day),
+    )
+    .ok_or(Error::InvalidInputData)
+}
+
+/// Convert the RTC time to `NaiveTime`.
+fn to_naive_time<E>(datetime: &DateTime) -> Result<NaiveTime, Error<E>> {
+    NaiveTime::from_hms_opt(
+        u32::from(datetime.hours),
+        u32::from(datetime.minutes),
+        u32::from(datetime.seconds),
+    )
+    .ok_or(Error::InvalidInputData)
+}
+
+/// Convert the year to the RTC two-digit year.
+fn to_rtc_year<E>(year: i32, mapping: YearMapping) -> Result<u8, Error<E>> {
+    mapping.two_digit_year(year).ok_or(Error::InvalidInputData)
+}
+
+impl<I2C, E> DateTimeAccess for PCF8563<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    type Error = Error<E>;
+
+    fn datetime(&mut self) -> Result<NaiveDateTime, Self::Error> {
+        let datetime = self.get_datetime()?;
+        Ok(NaiveDateTime::new(
+            to_naive_date(&datetime, self.year_mapping)?,
+            to_naive_time(&datetime)?,
+        ))
+    }
+
+    fn set_datetime(&mut self, datetime: &NaiveDateTime) -> Result<(), Self::Error> {
+        PCF8563::set_datetime(
+            self,
+            &DateTime {
+                year: to_rtc_year(datetime.year(), self.year_mapping)?,
+                month: datetime.month() as u8,
+                weekday: datetime.weekday().num_days_from_sunday() as u8,
+                day: datetime.day() as u8,
+                hours: datetime.hour() as u8,
+                minutes: datetime.minute() as u8,
+                seconds: datetime.second() as u8,
+            },
+        )
+    }
+}
+
+impl<I2C, E> Rtcc for PCF8563<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    fn seconds(&mut self) -> Result<u8, Self::Error> {
+        Ok(self.get_datetime()?.seconds)
+    }
+
+    fn minutes(&mut self) -> Result<u8, Self::Error> {
+        Ok(self.get_datetime()?.minutes)
+    }
+
+    fn hours(&mut self) -> Result<Hours, Self::Error> {
+        Ok(Hours::H24(self.get_datetime()?.hours))
+    }
+
+    fn time(&mut self) -> Result<NaiveTime, Self::Error> {
+        to_naive_time(&self.get_datetime()?)
+    }
+
+    fn weekday(&mut self) -> Result<u8, Self::Error> {
+        Ok(self.get_datetime()?.weekday + 1)
+    }
+
+    fn day(&mut self) -> Result<u8, Self::Error> {
+        Ok(self.get_datetime()?.day)
+    }
+
+    fn month(&mut self) -> Result<u8, Self::Error> {
+        Ok(self.get_datetime()?.month)
+    }
+
+    fn year(&mut self) -> Result<u16, Self::Error> {
+        self.get_full_year()
+    }
+
+    fn date(&mut self) -> Result<NaiveDate, Self::Error> {
+        to_naive_date(&self.get_datetime()?, self.year_mapping)
+    }
+
+    fn set_seconds(&mut self, seconds: u8) -> Result<(), Self::Error> {
+        self.update_datetime(|datetime| datetime.seconds = seconds)
+    }
+
+    fn set_minutes(&mut self, minutes: u8) -> Result<(), Self::Error> {
+        self.update_datetime(|datetime| datetime.minutes = minutes)
+    }
+
+    fn set_hours(&mut self, hours: Hours) -> Result<(), Self::Error> {
+        let hours = match hours {
+            Hours::H24(h) => h,
+            Hours::AM(h) if (1..=12).contains(&h) => h % 12,
+            Hours::PM(h) if (1..=12).contains(&h) => h % 12 + 12,
+            _ => return Err(Error::InvalidInputData),
+        };
+        self.update_datetime(|datetime| datetime.hours = hours)
+    }
+
+    fn set_time(&mut self, time: &NaiveTime) -> Result<(), Self::Error> {
+        PCF8563::set_time(
+            self,
+            &Time {
+                hours: time.hour() as u8,
+                minutes: time.minute() as u8,
+                seconds: time.second() as u8,
+            },
+        )
+    }
+
+    fn set_weekday(&mut self, weekday: u8) -> Result<(), Self::Error> {
+        if !(1..=7).contains(&weekday) {
+            return Err(Error::InvalidInputData);
+        }
+        self.update_datetime(|datetime| datetime.weekday = weekday - 1)
+    }
+
+    fn set_day(&mut self, day: u8) -> Result<(), Self::Error> {
+        self.update_datetime(|datetime| datetime.day = day)
+    }
+
+    fn set_month(&mut self, month: u8) -> Result<(), Self::Error> {
+        self.update_datetime(|datetime| datetime.month = month)
+    }
+
+    fn set_year(&mut self, year: u16) -> Result<(), Self::Error> {
+        let year = to_rtc_year(i32::from(year), self.year_mapping)?;
+        self.update_datetime(|datetime| datetime.year = year)
+    }
+
+    fn set_date(&mut self, date: &NaiveDate) -> Result<(), Self::Error> {
+        let year = to_rtc_year(date.year(), self.year_mapping)?;
+        self.update_datetime(|datetime| {
+            datetime.year = year;
+            datetime.month = date.month() as u8;
+            datetime.weekday = date.weekday().num_days_from_sunday() as u8;
+            datetime.day = date.day() as u8;
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::I2cMock;
+
+    #[test]
+    fn can_read_datetime() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x42, 0x15, 0x07, 0x04, 0x00, 0x04, 0x21]);
+        let mut rtc = PCF8563::new(i2c);
+        let expected = NaiveDate::from_ymd_opt(2021, 4, 4)
+            .unwrap()
+            .and_hms_opt(7, 15, 42)
+            .unwrap();
+        assert_eq!(DateTimeAccess::datetime(&mut rtc).unwrap(), expected);
+    }
+
+    #[test]
+    fn can_set_datetime() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        let datetime = NaiveDate::from_ymd_opt(2021, 4, 4)
+            .unwrap()
+            .and_hms_opt(7, 15, 42)
+            .unwrap();
+        DateTimeAccess::set_datetime(&mut rtc, &datetime).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(
+            i2c.get_write_data(),
+            &[0x02, 0x42, 0x15, 0x07, 0x04, 0x00, 0x04, 0x21]
+        );
+    }
+}
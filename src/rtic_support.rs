@@ -0,0 +1,152 @@
+//! Coarse monotonic clock for RTIC, backed by the RTC (requires the `rtic` feature).
+//!
+//! `RtcMonotonic` implements `rtic_monotonic::Monotonic` with a resolution of one second,
+//! so that tasks can be scheduled without keeping a hardware timer of the microcontroller
+//! running, e.g. in applications which sleep most of the time. The interrupt pin of the RTC
+//! is wired to the interrupt bound to the monotonic:
+//!
+//! - tasks due within 255 minutes are scheduled with the countdown timer
+//! - tasks due later are scheduled with the alarm, up to 27 days ahead, with the timer
+//!   re-armed when the alarm goes off
+//!
+//! ```rust,ignore
+//! #[monotonic(binds = EXTI0, default = true)]
+//! type RtcMono = pcf8563::RtcMonotonic<I2c>;
+//!
+//! some_task::spawn_after(30.secs()).unwrap();
+//! ```
+//!
+//! The trait methods can't return errors, so the I2C errors are ignored: `now()` then returns
+//! the last time read. The instants are Unix timestamps and never go backwards, even if the
+//! RTC is set to an earlier time, in which case the monotonic stalls until it catches up.
+//!
+//! The same operations are available as `now_instant()`, `schedule()` and `clear_flags()`,
+//! e.g. to build a timer queue backend for `rtic-monotonics`.
+
+use super::{hal, Control, PCF8563};
+use core::time::Duration;
+use fugit::{TimerDurationU32, TimerInstantU32};
+use hal::blocking::i2c::{Write, WriteRead};
+use rtic_monotonic::Monotonic;
+
+/// Longest delay scheduled with the countdown timer, in seconds.
+const MAX_TIMER_SECONDS: u32 = 255 * 60;
+
+/// Longest delay scheduled with the alarm, in seconds, shorter than any month
+/// so that the day alarm can't match earlier.
+const MAX_ALARM_SECONDS: u32 = 27 * 24 * 3600;
+
+/// Monotonic clock counting the seconds of the RTC.
+#[derive(Debug)]
+pub struct RtcMonotonic<I2C> {
+    rtc: PCF8563<I2C>,
+    last: u32,
+}
+
+impl<I2C, E> RtcMonotonic<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Create a new monotonic from the driver.
+    ///
+    /// The date and time must be set, as the instants are Unix timestamps.
+    pub fn new(rtc: PCF8563<I2C>) -> Self {
+        RtcMonotonic { rtc, last: 0 }
+    }
+
+    /// Access the driver, e.g. to read the date and time.
+    pub fn rtc(&mut self) -> &mut PCF8563<I2C> {
+        &mut self.rtc
+    }
+
+    /// Destroy the monotonic and return the driver.
+    pub fn destroy(self) -> PCF8563<I2C> {
+        self.rtc
+    }
+
+    /// Current instant, the Unix timestamp of the RTC.
+    pub fn now_instant(&mut self) -> TimerInstantU32<1> {
+        if let Ok(now) = self.rtc.get_unix_timestamp() {
+            if now > i64::from(self.last) && now <= i64::from(u32::MAX) {
+                self.last = now as u32;
+            }
+        }
+        TimerInstantU32::from_ticks(self.last)
+    }
+
+    /// Program the timer or the alarm to set off the interrupt at the instant,
+    /// at least one second from now.
+    pub fn schedule(&mut self, instant: TimerInstantU32<1>) {
+        let now = self.now_instant();
+        let delay = instant
+            .checked_duration_since(now)
+            .map_or(1, |delay| delay.ticks().max(1));
+        let _ = self.rtc.stop_and_clear_timer(false);
+        if delay <= MAX_TIMER_SECONDS {
+            let _ = self
+                .rtc
+                .set_timer_duration(Duration::from_secs(u64::from(delay)))
+                .and_then(|_| self.rtc.control_timer(Control::On))
+                .and_then(|_| self.rtc.control_timer_interrupt(Control::On));
+        } else {
+            let delay = delay.min(MAX_ALARM_SECONDS);
+            let _ = self.rtc.alarm_in(Duration::from_secs(u64::from(delay)));
+        }
+    }
+
+    /// Clear the timer and alarm flags, releasing the interrupt pin.
+    pub fn clear_flags(&mut self) {
+        let _ = self.rtc.clear_all_interrupt_flags();
+    }
+}
+
+impl<I2C, E> Monotonic for RtcMonotonic<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    type Instant = TimerInstantU32<1>;
+    type Duration = TimerDurationU32<1>;
+
+    fn now(&mut self) -> Self::Instant {
+        self.now_instant()
+    }
+
+    fn set_compare(&mut self, instant: Self::Instant) {
+        self.schedule(instant);
+    }
+
+    fn clear_compare_flag(&mut self) {
+        self.clear_flags();
+    }
+
+    fn zero() -> Self::Instant {
+        TimerInstantU32::from_ticks(0)
+    }
+
+    // the method is unsafe in the trait, the implementation has nothing unsafe to do
+    #[allow(unsafe_code)]
+    unsafe fn reset(&mut self) {
+        // the RTC keeps running, the instants are not reset
+        self.now_instant();
+    }
+
+    fn disable_timer(&mut self) {
+        let _ = self.rtc.stop_and_clear_timer(true);
+        let _ = self.rtc.control_alarm_interrupt(Control::Off);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::I2cMock;
+
+    #[test]
+    fn now_is_unix_timestamp_of_rtc() {
+        let mut i2c = I2cMock::new();
+        // 2021-04-04 07:15:42
+        i2c.set_read_data(&[0x42, 0x15, 0x07, 0x04, 0x00, 0x04, 0x21]);
+        let mut mono = RtcMonotonic::new(PCF8563::new(i2c));
+        assert_eq!(mono.now().ticks(), 1_617_520_542);
+    }
+}
@@ -0,0 +1,499 @@
+//! Alarm schedules which can't be expressed by the alarm registers alone.
+//!
+//! The alarm of the RTC only matches the minutes, hours, day and weekday fields.
+//! The helpers in this module program the next occurrence of the schedule and,
+//! when serviced after the alarm flag is set, program the one after it.
+
+use super::{
+    calendar, hal, AlarmConfig, Control, DateTime, Error, OutOfRangeError, Weekday, YearMapping,
+    PCF8563,
+};
+use core::time::Duration;
+use hal::blocking::i2c::{Write, WriteRead};
+
+/// Longest interval, as the day alarm matches again after the shortest month.
+const MAX_INTERVAL_SECONDS: u64 = 28 * 86_400;
+
+/// Alarm repeating at a fixed interval, e.g. every 6 hours.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecurringAlarm {
+    interval: i64,
+    next: Option<i64>,
+    mapping: YearMapping,
+}
+
+impl RecurringAlarm {
+    /// Create a recurring alarm with the given interval, in whole minutes from 1 minute to 28 days.
+    ///
+    /// Will return an `OutOfRangeError` if the interval is out of range or not in whole minutes.
+    pub fn new(interval: Duration) -> Result<Self, OutOfRangeError> {
+        let seconds = interval.as_secs();
+        let whole_minutes = Duration::from_secs(seconds / 60 * 60);
+        if seconds == 0 || seconds > MAX_INTERVAL_SECONDS || interval != whole_minutes {
+            return Err(OutOfRangeError);
+        }
+        Ok(RecurringAlarm {
+            interval: seconds as i64,
+            next: None,
+            mapping: YearMapping::Y2000,
+        })
+    }
+
+    /// Interval between the alarms.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval as u64)
+    }
+
+    /// Date and time of the next alarm, if started.
+    pub fn next(&self) -> Option<DateTime> {
+        self.next
+            .and_then(|next| DateTime::from_unix_timestamp_with(next, self.mapping).ok())
+    }
+
+    /// Clear the alarm flag, set the alarm one interval from the current minute and enable
+    /// the alarm interrupt. Returns the date and time of the alarm.
+    pub fn start<I2C, E>(&mut self, rtc: &mut PCF8563<I2C>) -> Result<DateTime, Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        let now = rtc.get_unix_timestamp()?;
+        rtc.clear_alarm_flag()?;
+        let target = self.arm(rtc, now - now % 60 + self.interval)?;
+        rtc.control_alarm_interrupt(Control::On)?;
+        Ok(target)
+    }
+
+    /// Check the alarm flag and, if set, clear it and set the next alarm.
+    ///
+    /// The next alarm is counted from the previous one, so the schedule doesn't drift.
+    /// Occurrences missed while the alarm was not serviced are skipped.
+    /// Returns the date and time of the next alarm, or `None` if the flag was not set.
+    pub fn service<I2C, E>(&mut self, rtc: &mut PCF8563<I2C>) -> Result<Option<DateTime>, Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        if !rtc.get_alarm_flag()? {
+            return Ok(None);
+        }
+        rtc.clear_alarm_flag()?;
+        let now = rtc.get_unix_timestamp()?;
+        let mut next = self.next.unwrap_or(now - now % 60);
+        while next <= now {
+            next += self.interval;
+        }
+        self.arm(rtc, next).map(Some)
+    }
+
+    /// Stop the schedule, disabling the alarm interrupt.
+    pub fn stop<I2C, E>(&mut self, rtc: &mut PCF8563<I2C>) -> Result<(), Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        self.next = None;
+        rtc.control_alarm_interrupt(Control::Off)
+    }
+
+    fn arm<I2C, E>(&mut self, rtc: &mut PCF8563<I2C>, next: i64) -> Result<DateTime, Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        self.mapping = rtc.get_year_mapping();
+        let target = DateTime::from_unix_timestamp_with(next, self.mapping)?;
+        rtc.set_alarm_at(&target)?;
+        self.next = Some(next);
+        Ok(target)
+    }
+}
+
+/// Alarm at a fixed time on selected weekdays, e.g. Monday to Friday at 07:00.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeekdaySchedule {
+    weekdays: u8,
+    hours: u8,
+    minutes: u8,
+}
+
+impl WeekdaySchedule {
+    /// Monday to Friday.
+    pub const WORKDAYS: u8 = Weekday::Monday.mask()
+        | Weekday::Tuesday.mask()
+        | Weekday::Wednesday.mask()
+        | Weekday::Thursday.mask()
+        | Weekday::Friday.mask();
+    /// Saturday and Sunday.
+    pub const WEEKEND: u8 = Weekday::Saturday.mask() | Weekday::Sunday.mask();
+    /// Every day of the week.
+    pub const EVERY_DAY: u8 = Self::WORKDAYS | Self::WEEKEND;
+
+    /// Create a schedule from a weekday bitmask (bit 0: Sunday, see `Weekday::mask()`) and a time.
+    ///
+    /// Will return an `OutOfRangeError` if no weekday is selected or the time is out of range.
+    pub fn new(weekdays: u8, hours: u8, minutes: u8) -> Result<Self, OutOfRangeError> {
+        if weekdays & Self::EVERY_DAY == 0 || weekdays & !Self::EVERY_DAY != 0 {
+            return Err(OutOfRangeError);
+        }
+        if hours > 23 || minutes > 59 {
+            return Err(OutOfRangeError);
+        }
+        Ok(WeekdaySchedule {
+            weekdays,
+            hours,
+            minutes,
+        })
+    }
+
+    /// Is the weekday selected?
+    pub fn includes(&self, weekday: Weekday) -> bool {
+        self.weekdays & weekday.mask() != 0
+    }
+
+    /// Date and time of the first occurrence strictly after the given one.
+    pub fn next_after(&self, datetime: &DateTime) -> Result<DateTime, OutOfRangeError> {
+        self.next_after_with(datetime, YearMapping::default())
+    }
+
+    /// Same as `next_after()`, mapping the year as given.
+    pub fn next_after_with(
+        &self,
+        datetime: &DateTime,
+        mapping: YearMapping,
+    ) -> Result<DateTime, OutOfRangeError> {
+        let now = datetime.to_unix_timestamp_with(mapping)?;
+        let midnight = now - now.rem_euclid(calendar::SECONDS_PER_DAY);
+        let time = i64::from(self.hours) * 3600 + i64::from(self.minutes) * 60;
+        let days = (0..=7)
+            .find(|days| {
+                let weekday = (datetime.weekday + days) % 7;
+                self.weekdays & (1 << weekday) != 0 && (*days > 0 || midnight + time > now)
+            })
+            .ok_or(OutOfRangeError)?;
+        DateTime::from_unix_timestamp_with(
+            midnight + i64::from(days) * calendar::SECONDS_PER_DAY + time,
+            mapping,
+        )
+    }
+
+    /// Clear the alarm flag, set the weekday and time alarm to the next occurrence and enable
+    /// the alarm interrupt. Returns the date and time of the alarm.
+    pub fn start<I2C, E>(&self, rtc: &mut PCF8563<I2C>) -> Result<DateTime, Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        rtc.clear_alarm_flag()?;
+        let target = self.arm(rtc)?;
+        rtc.control_alarm_interrupt(Control::On)?;
+        Ok(target)
+    }
+
+    /// Check the alarm flag and, if set, clear it and set the alarm to the next selected weekday.
+    ///
+    /// Returns the date and time of the next alarm, or `None` if the flag was not set.
+    pub fn service<I2C, E>(&self, rtc: &mut PCF8563<I2C>) -> Result<Option<DateTime>, Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        if !rtc.get_alarm_flag()? {
+            return Ok(None);
+        }
+        rtc.clear_alarm_flag()?;
+        self.arm(rtc).map(Some)
+    }
+
+    fn arm<I2C, E>(&self, rtc: &mut PCF8563<I2C>) -> Result<DateTime, Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        let mapping = rtc.get_year_mapping();
+        let target = self.next_after_with(&rtc.get_datetime()?, mapping)?;
+        rtc.set_alarm(&AlarmConfig {
+            minutes: Some(self.minutes),
+            hours: Some(self.hours),
+            day: None,
+            weekday: Some(target.weekday),
+        })?;
+        Ok(target)
+    }
+}
+
+/// Scheduler of up to `N` events over the single alarm of the RTC.
+///
+/// The alarm is always set to the earliest pending event. Events have a resolution
+/// of one minute, the time of an event is rounded up to the next whole minute.
+/// The year mapping of the scheduler must be the one of the driver.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlarmScheduler<const N: usize> {
+    events: [Option<i64>; N],
+    mapping: YearMapping,
+}
+
+impl<const N: usize> Default for AlarmScheduler<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> AlarmScheduler<N> {
+    /// Create an empty scheduler, for the default year mapping.
+    pub const fn new() -> Self {
+        Self::with_year_mapping(YearMapping::Y2000)
+    }
+
+    /// Create an empty scheduler for the year mapping of the driver.
+    pub const fn with_year_mapping(mapping: YearMapping) -> Self {
+        AlarmScheduler {
+            events: [None; N],
+            mapping,
+        }
+    }
+
+    /// Add an event, returning its slot [0-N).
+    ///
+    /// Returns the date and time back if all the slots are taken or it can't be converted.
+    /// Call `arm()` afterwards, in case the event is the earliest one.
+    pub fn add(&mut self, at: &DateTime) -> Result<usize, DateTime> {
+        let timestamp = at.to_unix_timestamp_with(self.mapping).map_err(|_| *at)?;
+        let slot = self.events.iter().position(Option::is_none).ok_or(*at)?;
+        self.events[slot] = Some(timestamp + (60 - timestamp % 60) % 60);
+        Ok(slot)
+    }
+
+    /// Remove the event in the slot, returning whether there was one.
+    pub fn remove(&mut self, slot: usize) -> bool {
+        self.events.get_mut(slot).and_then(Option::take).is_some()
+    }
+
+    /// Date and time of the event in the slot, if any.
+    pub fn get(&self, slot: usize) -> Option<DateTime> {
+        let timestamp = (*self.events.get(slot)?)?;
+        DateTime::from_unix_timestamp_with(timestamp, self.mapping).ok()
+    }
+
+    /// Number of pending events.
+    pub fn len(&self) -> usize {
+        self.events.iter().flatten().count()
+    }
+
+    /// Are there no pending events?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Date and time of the earliest pending event, if any.
+    pub fn next(&self) -> Option<DateTime> {
+        let timestamp = self.events.iter().flatten().min()?;
+        DateTime::from_unix_timestamp_with(*timestamp, self.mapping).ok()
+    }
+
+    /// Set the alarm to the earliest pending event and enable the alarm interrupt,
+    /// or disable the alarm interrupt if there is none.
+    pub fn arm<I2C, E>(&self, rtc: &mut PCF8563<I2C>) -> Result<(), Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        match self.next() {
+            Some(next) => {
+                rtc.set_alarm_at(&next)?;
+                rtc.control_alarm_interrupt(Control::On)
+            }
+            None => rtc.control_alarm_interrupt(Control::Off),
+        }
+    }
+
+    /// Clear the alarm flag, remove the events which are due and set the alarm to the next one.
+    ///
+    /// Can be called when the alarm interrupt occurs, or polled.
+    /// Returns the slots of the events which were due.
+    pub fn service<I2C, E>(&mut self, rtc: &mut PCF8563<I2C>) -> Result<DueEvents<N>, Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        rtc.clear_alarm_flag()?;
+        let now = rtc.get_unix_timestamp()?;
+        let mut due = DueEvents {
+            due: [false; N],
+            slot: 0,
+        };
+        for (event, due) in self.events.iter_mut().zip(due.due.iter_mut()) {
+            if matches!(*event, Some(timestamp) if timestamp <= now) {
+                *event = None;
+                *due = true;
+            }
+        }
+        self.arm(rtc)?;
+        Ok(due)
+    }
+}
+
+/// Iterator over the slots of the events which were due, returned by `AlarmScheduler::service()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DueEvents<const N: usize> {
+    due: [bool; N],
+    slot: usize,
+}
+
+impl<const N: usize> Iterator for DueEvents<N> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.slot < N {
+            self.slot += 1;
+            if self.due[self.slot - 1] {
+                return Some(self.slot - 1);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::I2cMock;
+
+    #[test]
+    fn interval_must_be_whole_minutes() {
+        assert!(RecurringAlarm::new(Duration::from_secs(0)).is_err());
+        assert!(RecurringAlarm::new(Duration::from_secs(90)).is_err());
+        assert!(RecurringAlarm::new(Duration::from_secs(29 * 86_400)).is_err());
+        assert!(RecurringAlarm::new(Duration::from_secs(6 * 3600)).is_ok());
+    }
+
+    #[test]
+    fn service_sets_next_alarm_from_previous_one() {
+        let mut alarm = RecurringAlarm::new(Duration::from_secs(6 * 3600)).unwrap();
+        let mut i2c = I2cMock::new();
+        // 2021-04-30 20:00:05
+        i2c.set_read_data(&[0x05, 0x00, 0x20, 0x30, 0x05, 0x04, 0x21]);
+        let mut rtc = PCF8563::new(i2c);
+        let first = alarm.start(&mut rtc).unwrap();
+        assert_eq!((first.month, first.day, first.hours), (5, 1, 2));
+
+        let mut i2c = rtc.destroy();
+        // AF set, 2021-05-01 02:00:03
+        i2c.set_read_data(&[0x08, 0x08, 0x03, 0x00, 0x02, 0x01, 0x06, 0x05, 0x21]);
+        let mut rtc = PCF8563::new(i2c);
+        let next = alarm.service(&mut rtc).unwrap().unwrap();
+        assert_eq!((next.day, next.hours, next.minutes), (1, 8, 0));
+        assert_eq!(alarm.next(), Some(next));
+    }
+
+    #[test]
+    fn weekday_schedule_skips_weekend() {
+        let schedule = WeekdaySchedule::new(WeekdaySchedule::WORKDAYS, 7, 0).unwrap();
+        assert!(!schedule.includes(Weekday::Sunday));
+        // Friday 2021-04-30 07:00:00
+        let friday = DateTime {
+            year: 21,
+            month: 4,
+            day: 30,
+            weekday: 5,
+            hours: 7,
+            minutes: 0,
+            seconds: 0,
+        };
+        let next = schedule.next_after(&friday).unwrap();
+        assert_eq!(
+            (next.month, next.day, next.weekday, next.hours),
+            (5, 3, 1, 7)
+        );
+        let earlier = DateTime {
+            hours: 6,
+            minutes: 59,
+            ..friday
+        };
+        assert_eq!(schedule.next_after(&earlier).unwrap().day, 30);
+    }
+
+    #[test]
+    fn weekday_schedule_sets_weekday_alarm() {
+        let schedule = WeekdaySchedule::new(Weekday::Monday.mask(), 7, 30).unwrap();
+        let mut i2c = I2cMock::new();
+        // AF set, Monday 2021-05-03 07:30:01
+        i2c.set_read_data(&[0x08, 0x08, 0x01, 0x30, 0x07, 0x03, 0x01, 0x05, 0x21]);
+        let mut rtc = PCF8563::new(i2c);
+        let next = schedule.service(&mut rtc).unwrap().unwrap();
+        assert_eq!((next.day, next.weekday), (10, 1));
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[0x09, 0x30, 0x07, 0x80, 0x01]);
+    }
+
+    #[test]
+    fn cannot_create_empty_weekday_schedule() {
+        assert!(WeekdaySchedule::new(0, 7, 0).is_err());
+        assert!(WeekdaySchedule::new(0x80, 7, 0).is_err());
+        assert!(WeekdaySchedule::new(WeekdaySchedule::WEEKEND, 24, 0).is_err());
+    }
+
+    fn at(day: u8, hours: u8, minutes: u8) -> DateTime {
+        DateTime {
+            year: 21,
+            month: 4,
+            day,
+            weekday: 0,
+            hours,
+            minutes,
+            seconds: 0,
+        }
+    }
+
+    #[test]
+    fn scheduler_has_fixed_capacity() {
+        let mut scheduler = AlarmScheduler::<2>::new();
+        assert_eq!(scheduler.add(&at(30, 12, 0)), Ok(0));
+        assert_eq!(scheduler.add(&at(30, 8, 0)), Ok(1));
+        assert_eq!(scheduler.add(&at(30, 9, 0)), Err(at(30, 9, 0)));
+        assert_eq!(scheduler.next().unwrap().hours, 8);
+        assert!(scheduler.remove(1));
+        assert_eq!(scheduler.len(), 1);
+        assert_eq!(scheduler.add(&at(30, 9, 0)), Ok(1));
+    }
+
+    #[test]
+    fn scheduler_uses_year_mapping() {
+        let mut scheduler =
+            AlarmScheduler::<1>::with_year_mapping(YearMapping::new(2100, 100).unwrap());
+        // 2100 is not a leap year
+        let event = DateTime {
+            year: 0,
+            month: 2,
+            day: 28,
+            hours: 23,
+            minutes: 59,
+            seconds: 30,
+            ..at(1, 0, 0)
+        };
+        scheduler.add(&event).unwrap();
+        let next = scheduler.next().unwrap();
+        assert_eq!((next.month, next.day), (3, 1));
+    }
+
+    #[test]
+    fn scheduler_returns_due_events_and_sets_next() {
+        let mut scheduler = AlarmScheduler::<3>::new();
+        scheduler.add(&at(30, 12, 0)).unwrap();
+        scheduler.add(&at(30, 8, 0)).unwrap();
+        scheduler.add(&at(30, 8, 0)).unwrap();
+        let mut i2c = I2cMock::new();
+        // AF set, 2021-04-30 08:00:01
+        i2c.set_read_data(&[0x08, 0x01, 0x00, 0x08, 0x30, 0x05, 0x04, 0x21]);
+        let mut rtc = PCF8563::new(i2c);
+        let mut due = scheduler.service(&mut rtc).unwrap();
+        assert_eq!(
+            (due.next(), due.next(), due.next()),
+            (Some(1), Some(2), None)
+        );
+        assert_eq!(scheduler.len(), 1);
+        let i2c = rtc.destroy();
+        // alarm interrupt enabled after setting the alarm to 12:00
+        assert_eq!(i2c.get_write_data(), &[0x01, 0x0e]);
+    }
+
+    #[test]
+    fn service_does_nothing_without_flag() {
+        let mut alarm = RecurringAlarm::new(Duration::from_secs(60)).unwrap();
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x00]);
+        let mut rtc = PCF8563::new(i2c);
+        assert_eq!(alarm.service(&mut rtc).unwrap(), None);
+    }
+}
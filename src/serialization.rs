@@ -0,0 +1,220 @@
+//! Serialization formats for `DateTime` (requires the `serde` feature), to be selected
+//! with `#[serde(with = "...")]` on the fields of the application types:
+//!
+//! - [`rfc3339`]: human-readable RFC 3339 string in UTC, e.g. `"2021-04-04T07:15:00Z"`,
+//!   for configuration files or JSON
+//! - [`compact`]: 7 bytes (seconds, minutes, hours, day, weekday, month, year),
+//!   for binary formats like postcard
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "pcf8563::serialization::rfc3339")]
+//!     start: DateTime,
+//! }
+//! ```
+//!
+//! Without `#[serde(with = "...")]`, `DateTime` is serialized as a struct.
+//! The two-digit year is mapped to the years 2000-2099.
+//!
+//! `AlarmConfig` is serialized as a tuple of the four components (minutes, hours, day, weekday),
+//! a disabled component being `None`, e.g. `[30,7,null,null]` in JSON.
+
+use super::{calendar, AlarmConfig, DateTime};
+use core::fmt;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+impl Serialize for AlarmConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.minutes, self.hours, self.day, self.weekday).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AlarmConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (minutes, hours, day, weekday) = Deserialize::deserialize(deserializer)?;
+        let alarm = AlarmConfig {
+            minutes,
+            hours,
+            day,
+            weekday,
+        };
+        if !alarm.is_valid() {
+            return Err(de::Error::custom("alarm component out of range"));
+        }
+        Ok(alarm)
+    }
+}
+
+/// RFC 3339 representation of the date and time in UTC.
+struct Rfc3339<'a>(&'a DateTime);
+
+impl<'a> fmt::Display for Rfc3339<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            2000 + u16::from(self.0.year),
+            self.0.month,
+            self.0.day,
+            self.0.hours,
+            self.0.minutes,
+            self.0.seconds
+        )
+    }
+}
+
+/// Parse a RFC 3339 date and time in UTC ("Z" or "+00:00"), without fractional seconds.
+fn parse_rfc3339(input: &str) -> Option<DateTime> {
+    let bytes = input.as_bytes();
+    let offset = bytes.get(19..)?;
+    if offset != b"Z" && offset != b"z" && offset != b"+00:00" {
+        return None;
+    }
+    if bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || (bytes[10] != b'T' && bytes[10] != b't')
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return None;
+    }
+    let number = |start: usize, end: usize| -> Option<u16> {
+        bytes[start..end].iter().try_fold(0u16, |acc, &digit| {
+            if digit.is_ascii_digit() {
+                Some(acc * 10 + u16::from(digit - b'0'))
+            } else {
+                None
+            }
+        })
+    };
+    let year = number(0, 4)?;
+    if !(2000..=2099).contains(&year) {
+        return None;
+    }
+    let mut datetime = DateTime {
+        year: (year - 2000) as u8,
+        month: number(5, 7)? as u8,
+        weekday: 0,
+        day: number(8, 10)? as u8,
+        hours: number(11, 13)? as u8,
+        minutes: number(14, 16)? as u8,
+        seconds: number(17, 19)? as u8,
+    };
+    if !datetime.is_valid() {
+        return None;
+    }
+    datetime.weekday = calendar::weekday_from_days(calendar::days_from_civil(
+        i32::from(year),
+        datetime.month,
+        datetime.day,
+    ));
+    Some(datetime)
+}
+
+/// Human-readable RFC 3339 string in UTC, e.g. `"2021-04-04T07:15:00Z"`.
+///
+/// The weekday is not serialized, it is computed from the date when deserializing.
+pub mod rfc3339 {
+    use super::{parse_rfc3339, Rfc3339};
+    use crate::DateTime;
+    use core::fmt;
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+
+    /// Serialize the date and time as a RFC 3339 string.
+    pub fn serialize<S: Serializer>(datetime: &DateTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&Rfc3339(datetime))
+    }
+
+    /// Deserialize the date and time from a RFC 3339 string.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime, D::Error> {
+        struct Rfc3339Visitor;
+
+        impl<'de> Visitor<'de> for Rfc3339Visitor {
+            type Value = DateTime;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a RFC 3339 date and time in UTC between 2000 and 2099")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<DateTime, E> {
+                parse_rfc3339(value)
+                    .ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))
+            }
+        }
+
+        deserializer.deserialize_str(Rfc3339Visitor)
+    }
+}
+
+/// Compact binary form of 7 bytes: seconds, minutes, hours, day, weekday, month, year.
+pub mod compact {
+    use crate::DateTime;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serialize the date and time as 7 bytes.
+    pub fn serialize<S: Serializer>(datetime: &DateTime, serializer: S) -> Result<S::Ok, S::Error> {
+        [
+            datetime.seconds,
+            datetime.minutes,
+            datetime.hours,
+            datetime.day,
+            datetime.weekday,
+            datetime.month,
+            datetime.year,
+        ]
+        .serialize(serializer)
+    }
+
+    /// Deserialize the date and time from 7 bytes.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime, D::Error> {
+        let data = <[u8; 7]>::deserialize(deserializer)?;
+        let datetime = DateTime {
+            year: data[6],
+            month: data[5],
+            weekday: data[4],
+            day: data[3],
+            hours: data[2],
+            minutes: data[1],
+            seconds: data[0],
+        };
+        if !datetime.is_valid() {
+            return Err(D::Error::custom("date and time out of range"));
+        }
+        Ok(datetime)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+    use std::string::ToString;
+
+    const DATETIME: DateTime = DateTime {
+        year: 21,
+        month: 4,
+        weekday: 0,
+        day: 4,
+        hours: 7,
+        minutes: 15,
+        seconds: 0,
+    };
+
+    #[test]
+    fn can_format_rfc3339() {
+        assert_eq!(Rfc3339(&DATETIME).to_string(), "2021-04-04T07:15:00Z");
+    }
+
+    #[test]
+    fn can_parse_rfc3339() {
+        assert_eq!(parse_rfc3339("2021-04-04T07:15:00Z"), Some(DATETIME));
+        assert_eq!(parse_rfc3339("2021-04-04T07:15:00+00:00"), Some(DATETIME));
+        assert_eq!(parse_rfc3339("2021-04-04T07:15:00+01:00"), None);
+        assert_eq!(parse_rfc3339("2021-02-29T07:15:00Z"), None);
+        assert_eq!(parse_rfc3339("1999-04-04T07:15:00Z"), None);
+        assert_eq!(parse_rfc3339("2021-04-04"), None);
+    }
+}
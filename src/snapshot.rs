@@ -0,0 +1,148 @@
+//! Snapshots of all the registers, compared to find out what changed between two points in time.
+//!
+//! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let mut rtc = PCF8563::new(i2c);
+//! let before = rtc.snapshot().unwrap();
+//! // later
+//! let after = rtc.snapshot().unwrap();
+//! for change in before.diff(&after) {
+//!     // e.g. log change.name and change.changed_bits()
+//! }
+//! ```
+
+use super::{hal, Error, Register, PCF8563};
+use hal::blocking::i2c::{Write, WriteRead};
+
+/// Names of the registers, by address.
+const REGISTER_NAMES: [&str; 16] = [
+    "CTRL_STATUS_1",
+    "CTRL_STATUS_2",
+    "VL_SECONDS",
+    "MINUTES",
+    "HOURS",
+    "DAYS",
+    "WEEKDAYS",
+    "CENTURY_MONTHS",
+    "YEARS",
+    "MINUTE_ALARM",
+    "HOUR_ALARM",
+    "DAY_ALARM",
+    "WEEKDAY_ALARM",
+    "CLKOUT_CTRL",
+    "TIMER_CTRL",
+    "TIMER",
+];
+
+/// Values of all the registers, read with `snapshot()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterSnapshot {
+    registers: [u8; 16],
+}
+
+impl RegisterSnapshot {
+    /// Create a snapshot from the values of the registers, by address.
+    pub fn from_registers(registers: [u8; 16]) -> Self {
+        RegisterSnapshot { registers }
+    }
+
+    /// Values of the registers, by address.
+    pub fn registers(&self) -> &[u8; 16] {
+        &self.registers
+    }
+
+    /// Registers which are different in the other snapshot, taken later.
+    ///
+    /// The time registers and the timer value change as the RTC runs.
+    pub fn diff<'a>(&'a self, other: &'a RegisterSnapshot) -> RegisterDiff<'a> {
+        RegisterDiff {
+            before: self,
+            after: other,
+            address: 0,
+        }
+    }
+}
+
+/// Change of a register between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterChange {
+    /// Address of the register.
+    pub address: u8,
+    /// Name of the register, as in the datasheet.
+    pub name: &'static str,
+    /// Value in the first snapshot.
+    pub before: u8,
+    /// Value in the other snapshot.
+    pub after: u8,
+}
+
+impl RegisterChange {
+    /// Mask of the bits which changed.
+    pub fn changed_bits(&self) -> u8 {
+        self.before ^ self.after
+    }
+}
+
+/// Iterator over the changed registers, created by `RegisterSnapshot::diff()`.
+#[derive(Debug, Clone)]
+pub struct RegisterDiff<'a> {
+    before: &'a RegisterSnapshot,
+    after: &'a RegisterSnapshot,
+    address: usize,
+}
+
+impl<'a> Iterator for RegisterDiff<'a> {
+    type Item = RegisterChange;
+
+    fn next(&mut self) -> Option<RegisterChange> {
+        while self.address < REGISTER_NAMES.len() {
+            let address = self.address;
+            self.address += 1;
+            let before = self.before.registers[address];
+            let after = self.after.registers[address];
+            if before != after {
+                return Some(RegisterChange {
+                    address: address as u8,
+                    name: REGISTER_NAMES[address],
+                    before,
+                    after,
+                });
+            }
+        }
+        None
+    }
+}
+
+impl<I2C, E> PCF8563<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Read all the registers in one transaction.
+    pub fn snapshot(&mut self) -> Result<RegisterSnapshot, Error<E>> {
+        let mut registers = [0; 16];
+        self.read_registers(Register::CTRL_STATUS_1, &mut registers)?;
+        Ok(RegisterSnapshot { registers })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_lists_changed_registers() {
+        let mut registers = [0; 16];
+        let before = RegisterSnapshot::from_registers(registers);
+        registers[2] = 0x01;
+        registers[13] = 0x80;
+        let after = RegisterSnapshot::from_registers(registers);
+        let mut diff = before.diff(&after);
+        assert_eq!(diff.next().unwrap().name, "VL_SECONDS");
+        let change = diff.next().unwrap();
+        assert_eq!(change.address, Register::CLKOUT_CTRL);
+        assert_eq!(change.changed_bits(), 0x80);
+        assert_eq!(diff.next(), None);
+        assert_eq!(before.diff(&before).count(), 0);
+    }
+}
@@ -0,0 +1,84 @@
+//! Conversions between `DateTime` and `std::time::SystemTime`, and helpers
+//! to compare and synchronize the RTC with the system clock (requires the `std` feature).
+//!
+//! The RTC is expected to hold the UTC time. The conversions map the two-digit year to the years 2000-2099,
+//! the driver helpers use the year mapping of the driver.
+
+use super::{hal, DateTime, Error, OutOfRangeError, PCF8563};
+use core::convert::TryFrom;
+use hal::blocking::i2c::{Write, WriteRead};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+impl TryFrom<SystemTime> for DateTime {
+    type Error = OutOfRangeError;
+
+    /// Convert the system time, sub-second precision is dropped.
+    fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+        let timestamp = time
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| OutOfRangeError)?;
+        DateTime::from_unix_timestamp(timestamp.as_secs() as i64)
+    }
+}
+
+impl TryFrom<DateTime> for SystemTime {
+    type Error = OutOfRangeError;
+
+    fn try_from(datetime: DateTime) -> Result<Self, Self::Error> {
+        let timestamp = datetime.to_unix_timestamp()?;
+        Ok(UNIX_EPOCH + Duration::from_secs(timestamp as u64))
+    }
+}
+
+impl<I2C, E> PCF8563<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Set the RTC date and time from the system clock (like `hwclock --systohc`).
+    pub fn sync_from_system_clock(&mut self) -> Result<(), Error<E>> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| Error::InvalidInputData)?;
+        self.set_unix_timestamp(now.as_secs() as i64)
+    }
+
+    /// Difference in seconds between the RTC and the system clock
+    /// (positive if the RTC is ahead of the system clock).
+    pub fn system_clock_offset(&mut self) -> Result<i64, Error<E>> {
+        let rtc = self.get_unix_timestamp()?;
+        let system = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| Error::InvalidInputData)?;
+        Ok(rtc - system.as_secs() as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_convert_system_time() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_617_520_542);
+        let datetime = DateTime::try_from(time).unwrap();
+        assert_eq!(
+            datetime,
+            DateTime {
+                year: 21,
+                month: 4,
+                weekday: 0,
+                day: 4,
+                hours: 7,
+                minutes: 15,
+                seconds: 42,
+            }
+        );
+        assert_eq!(SystemTime::try_from(datetime), Ok(time));
+    }
+
+    #[test]
+    fn cannot_convert_system_time_before_2000() {
+        let time = UNIX_EPOCH + Duration::from_secs(946_684_799);
+        assert_eq!(DateTime::try_from(time), Err(OutOfRangeError));
+    }
+}
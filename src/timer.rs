@@ -1,7 +1,14 @@
 //! All timer-related functions will be defined here
 
-use super::{hal, BitFlags, Control, Error, Register, DEVICE_ADDRESS, PCF8563};
-use hal::blocking::i2c::{Write, WriteRead};
+use super::{
+    alarm::alarm_in_target, hal, BitFlags, Control, DateTime, Error, OutOfRangeError, Register,
+    YearMapping, DEVICE_ADDRESS, PCF8563,
+};
+use core::time::Duration;
+use hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
 
 /// Four possible timer frequency settings.
 #[allow(non_camel_case_types)]
@@ -23,11 +30,129 @@ impl TimerFreq {
     pub fn bits(self) -> u8 {
         self as u8
     }
+
+    /// Decode the frequency from the timer control register.
+    pub(crate) fn from_bits(data: u8) -> Self {
+        match data & 0b0000_0011 {
+            0b00 => TimerFreq::Timer_4096Hz,
+            0b01 => TimerFreq::Timer_64Hz,
+            0b10 => TimerFreq::Timer_1Hz,
+            _ => TimerFreq::Timer_1_60Hz,
+        }
+    }
+
+    /// Frequency as a ratio: number of ticks, and number of seconds in which they occur.
+    pub(crate) const fn rate(self) -> (u128, u128) {
+        match self {
+            TimerFreq::Timer_4096Hz => (4096, 1),
+            TimerFreq::Timer_64Hz => (64, 1),
+            TimerFreq::Timer_1Hz => (1, 1),
+            TimerFreq::Timer_1_60Hz => (1, 60),
+        }
+    }
+
+    /// Number of ticks closest to the duration.
+    fn ticks(self, duration: Duration) -> u128 {
+        let (ticks, seconds) = self.rate();
+        let period = seconds * 1_000_000_000;
+        (duration.as_nanos() * ticks + period / 2) / period
+    }
+
+    /// Duration of the number of ticks.
+    pub(crate) const fn duration(self, ticks: u8) -> Duration {
+        let (rate_ticks, seconds) = self.rate();
+        let nanos = ticks as u128 * seconds * 1_000_000_000 / rate_ticks;
+        Duration::from_nanos(nanos as u64)
+    }
+
+    /// Duration of one tick, rounded down to the nanosecond (e.g. 244.140 µs at 4096 Hz).
+    pub const fn resolution(self) -> Duration {
+        self.duration(1)
+    }
+
+    /// Longest duration of the timer, 255 ticks (e.g. 62.255 ms at 4096 Hz).
+    pub const fn max_duration(self) -> Duration {
+        self.duration(255)
+    }
+}
+
+/// Timer frequencies, from the finest to the coarsest resolution.
+const TIMER_FREQUENCIES: [TimerFreq; 4] = [
+    TimerFreq::Timer_4096Hz,
+    TimerFreq::Timer_64Hz,
+    TimerFreq::Timer_1Hz,
+    TimerFreq::Timer_1_60Hz,
+];
+
+/// Frequency and value of the timer for the duration, at the finest resolution which can hold it.
+fn timer_setting(duration: Duration) -> Option<(TimerFreq, u8)> {
+    if duration == Duration::from_secs(0) {
+        return None;
+    }
+    TIMER_FREQUENCIES
+        .iter()
+        .map(|frequency| (*frequency, frequency.ticks(duration).max(1)))
+        .find(|(_, ticks)| *ticks <= 255)
+        .map(|(frequency, ticks)| (frequency, ticks as u8))
+}
+
+/// Requested and achievable duration, as programmed by `set_timer_duration()` or `alarm_in()`.
+///
+/// Computed without accessing the RTC, so that the application can decide whether
+/// the approximation is acceptable before programming it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Quantization {
+    /// Requested duration.
+    pub requested: Duration,
+    /// Achievable duration.
+    pub actual: Duration,
+}
+
+impl Quantization {
+    /// Quantization of `set_timer_duration()`, the duration rounded to the nearest tick.
+    ///
+    /// Will return an `OutOfRangeError` if the duration is zero or longer than 255 minutes.
+    pub fn timer(duration: Duration) -> Result<Self, OutOfRangeError> {
+        let (frequency, ticks) = timer_setting(duration).ok_or(OutOfRangeError)?;
+        Ok(Quantization {
+            requested: duration,
+            actual: frequency.duration(ticks),
+        })
+    }
+
+    /// Quantization of `alarm_in()` at the given date and time, the duration rounded up
+    /// to the next whole minute.
+    ///
+    /// Will return an `OutOfRangeError` if the duration is longer than 28 days, if the alarm
+    /// would match earlier (see `alarm_in()`) or if the date and time is out of range.
+    pub fn alarm_in(now: &DateTime, duration: Duration) -> Result<Self, OutOfRangeError> {
+        Self::alarm_in_with(now, duration, YearMapping::default())
+    }
+
+    /// Same as `Quantization::alarm_in()`, mapping the year as given, e.g. the year mapping
+    /// of the driver.
+    pub fn alarm_in_with(
+        now: &DateTime,
+        duration: Duration,
+        mapping: YearMapping,
+    ) -> Result<Self, OutOfRangeError> {
+        let now = now.to_unix_timestamp_with(mapping)?;
+        let target = alarm_in_target(now, duration)?;
+        Ok(Quantization {
+            requested: duration,
+            actual: Duration::from_secs((target - now) as u64),
+        })
+    }
+
+    /// Absolute difference between the requested and achievable durations.
+    pub fn error(&self) -> Duration {
+        self.actual.max(self.requested) - self.actual.min(self.requested)
+    }
 }
 
 /// Two possible timer interrupt output modes
 #[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum InterruptOutput {
     /// Active when TF active (default setting).
     Continuous,
@@ -35,21 +160,223 @@ pub enum InterruptOutput {
     Pulsating,
 }
 
+/// Timer mode, deciding how the timer is handled by `service_timer()`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum TimerMode {
+    /// The timer is stopped when serviced, the interrupt output is continuous (default).
+    #[default]
+    OneShot,
+    /// The timer keeps running when serviced, the interrupt output is pulsating.
+    Periodic,
+}
+
+/// Timer configuration, read and written with `get_timer_config()`/`set_timer_config()`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TimerConfig {
+    /// Timer running.
+    pub enabled: bool,
+    /// Timer frequency.
+    pub frequency: TimerFreq,
+    /// Timer value, or remaining ticks when read.
+    pub ticks: u8,
+    /// Timer interrupt enabled.
+    pub interrupt: bool,
+    /// Pulsating interrupt output, otherwise continuous.
+    pub pulse_mode: bool,
+}
+
+/// Timer configuration and flag, read with `timer_state()`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TimerState {
+    /// Timer configuration, with the remaining ticks.
+    pub config: TimerConfig,
+    /// Timer flag set.
+    pub flag: bool,
+}
+
 impl<I2C, E> PCF8563<I2C>
 where
     I2C: Write<Error = E> + WriteRead<Error = E>,
 {
     /// Set the timer [0-255]
+    ///
+    /// If the timer is running, the countdown restarts from the new value.
     pub fn set_timer(&mut self, time: u8) -> Result<(), Error<E>> {
         self.write_register(Register::TIMER, time)
     }
 
+    /// Set the timer frequency and value for the duration, choosing the finest resolution
+    /// which can hold it (does not alter the timer enabled/disabled bit).
+    ///
+    /// Returns the actual duration, the requested one rounded to the nearest tick.
+    /// Will return an 'Error::InvalidInputData' if the duration is zero or longer than 255 minutes,
+    /// or an `Error::TimerRunning` if the timer is enabled.
+    pub fn set_timer_duration(&mut self, duration: Duration) -> Result<Duration, Error<E>> {
+        let (frequency, ticks) = timer_setting(duration).ok_or(Error::InvalidInputData)?;
+        self.set_timer_frequency(frequency)?;
+        self.set_timer(ticks)?;
+        Ok(frequency.duration(ticks))
+    }
+
+    /// Start the timer for the number of ticks at the frequency, with two writes and no reads:
+    /// the timer value, then the timer control register.
+    pub fn start_timer(&mut self, frequency: TimerFreq, ticks: u8) -> Result<(), Error<E>> {
+        self.set_timer(ticks)?;
+        self.write_register(Register::TIMER_CTRL, BitFlags::TE | frequency.bits())
+    }
+
+    /// Stop the timer, then clear the timer flag and optionally disable the timer interrupt
+    /// with a single write, so a stale flag can't trigger the interrupt pin again.
+    pub fn stop_and_clear_timer(&mut self, disable_interrupt: bool) -> Result<(), Error<E>> {
+        self.control_timer(Control::Off)?;
+        let data = self.read_register(Register::CTRL_STATUS_2)?;
+        let mut data = (data & (BitFlags::TI_TP | BitFlags::AIE | BitFlags::TIE)) | BitFlags::AF;
+        if disable_interrupt {
+            data &= !BitFlags::TIE;
+        }
+        self.write_register(Register::CTRL_STATUS_2, data)
+    }
+
+    /// Measure the error of the microcontroller clock against the RTC, in ppm (positive if
+    /// the microcontroller clock is fast).
+    ///
+    /// The timer runs for the number of ticks [1-255] at the frequency while `cycles` reads
+    /// a free-running (wrapping) cycle counter of the clock running at `clock_hz`, e.g. the
+    /// DWT cycle counter. The counter is read when the timer flag is seen set at the start
+    /// and at the end of one timer period, so the I2C latency mostly cancels out, and longer
+    /// periods give more accurate results. The timer is stopped and the flag cleared afterwards.
+    /// The result saturates at the limits of `i32`.
+    /// Will return an 'Error::InvalidInputData' if the number of ticks or the clock frequency
+    /// is zero, or if the clock doesn't count a single cycle during the timer period.
+    pub fn measure_clock_error<F>(
+        &mut self,
+        frequency: TimerFreq,
+        ticks: u8,
+        clock_hz: u32,
+        mut cycles: F,
+    ) -> Result<i32, Error<E>>
+    where
+        F: FnMut() -> u32,
+    {
+        let expected = u128::from(clock_hz) * frequency.duration(ticks).as_nanos() / 1_000_000_000;
+        if ticks == 0 || expected == 0 {
+            return Err(Error::InvalidInputData);
+        }
+        self.stop_and_clear_timer(false)?;
+        self.start_timer(frequency, ticks)?;
+        // the first period may be shorter, measure the second one
+        while !self.service_timer_flag()? {}
+        let start = cycles();
+        while !self.service_timer_flag()? {}
+        let measured = cycles().wrapping_sub(start);
+        self.stop_and_clear_timer(false)?;
+        let error = (i128::from(measured) - expected as i128) * 1_000_000 / expected as i128;
+        Ok(error.clamp(i128::from(i32::MIN), i128::from(i32::MAX)) as i32)
+    }
+
+    /// Clear the timer flag if set, returns whether it was set.
+    fn service_timer_flag(&mut self) -> Result<bool, Error<E>> {
+        let flag = self.get_timer_flag()?;
+        if flag {
+            self.clear_timer_flag()?;
+        }
+        Ok(flag)
+    }
+
+    /// Start the timer repeating every number of ticks [1-255] at the frequency, with the timer
+    /// interrupt enabled in pulsating mode, in three transactions.
+    ///
+    /// The timer flag is cleared, the alarm interrupt setting and flag are kept.
+    /// Will return an 'Error::InvalidInputData' if the number of ticks is zero.
+    pub fn start_periodic(&mut self, frequency: TimerFreq, ticks: u8) -> Result<(), Error<E>> {
+        if ticks == 0 {
+            return Err(Error::InvalidInputData);
+        }
+        let data = self.read_register(Register::CTRL_STATUS_2)?;
+        let data = (data & BitFlags::AIE) | BitFlags::AF | BitFlags::TI_TP | BitFlags::TIE;
+        self.write_register(Register::CTRL_STATUS_2, data)?;
+        self.write_registers(
+            Register::TIMER_CTRL,
+            &[BitFlags::TE | frequency.bits(), ticks],
+        )?;
+        self.timer_mode = TimerMode::Periodic;
+        Ok(())
+    }
+
+    /// Enable an interrupt every minute: timer at 1/60 Hz with the value 1, timer interrupt
+    /// enabled in pulsating mode.
+    ///
+    /// Note that the timer runs independently of the time registers, so the interrupt
+    /// is not aligned with the start of the minute.
+    pub fn enable_minute_interrupt(&mut self) -> Result<(), Error<E>> {
+        self.start_periodic(TimerFreq::Timer_1_60Hz, 1)
+    }
+
+    /// Disable the interrupt every minute, stopping the timer and disabling the timer interrupt.
+    pub fn disable_minute_interrupt(&mut self) -> Result<(), Error<E>> {
+        self.stop_periodic()
+    }
+
+    /// Enable an interrupt every second: timer at 1 Hz with the value 1, timer interrupt
+    /// enabled in pulsating mode.
+    ///
+    /// Note that the timer runs independently of the time registers, so the interrupt
+    /// is not aligned with the change of the seconds.
+    pub fn enable_second_interrupt(&mut self) -> Result<(), Error<E>> {
+        self.start_periodic(TimerFreq::Timer_1Hz, 1)
+    }
+
+    /// Disable the interrupt every second, stopping the timer and disabling the timer interrupt.
+    pub fn disable_second_interrupt(&mut self) -> Result<(), Error<E>> {
+        self.stop_periodic()
+    }
+
+    /// Stop the timer and disable the timer interrupt.
+    fn stop_periodic(&mut self) -> Result<(), Error<E>> {
+        self.control_timer(Control::Off)?;
+        self.control_timer_interrupt(Control::Off)
+    }
+
+    /// Set the timer mode, selecting the matching interrupt output mode.
+    pub fn set_timer_mode(&mut self, mode: TimerMode) -> Result<(), Error<E>> {
+        self.timer_interrupt_output(match mode {
+            TimerMode::OneShot => InterruptOutput::Continuous,
+            TimerMode::Periodic => InterruptOutput::Pulsating,
+        })?;
+        self.timer_mode = mode;
+        Ok(())
+    }
+
+    /// Get the timer mode.
+    pub fn get_timer_mode(&self) -> TimerMode {
+        self.timer_mode
+    }
+
+    /// Check the timer flag and, if set, clear it, also stopping the timer in one-shot mode.
+    ///
+    /// Returns whether the timer flag was set.
+    pub fn service_timer(&mut self) -> Result<bool, Error<E>> {
+        if !self.get_timer_flag()? {
+            return Ok(false);
+        }
+        if self.timer_mode == TimerMode::OneShot {
+            self.control_timer(Control::Off)?;
+        }
+        self.clear_timer_flag()?;
+        Ok(true)
+    }
+
     /// Set timer frequency (does not alter the timer enabled/disabled bit).
+    ///
+    /// Will return an `Error::TimerRunning` if the timer is enabled, as changing the frequency
+    /// during the countdown leaves the remaining time undefined.
     pub fn set_timer_frequency(&mut self, frequency: TimerFreq) -> Result<(), Error<E>> {
         let data = self.read_register(Register::TIMER_CTRL)?; // read current value
-        let data = data & 0b1000_0000; // keep the TE bit as is
-        let data = data | frequency.bits(); // set the lowest two bits
-        self.write_register(Register::TIMER_CTRL, data)
+        if data & BitFlags::TE != 0 {
+            return Err(Error::TimerRunning);
+        }
+        // TE is known to be clear and the other bits are unused
+        self.write_register(Register::TIMER_CTRL, frequency.bits())
     }
 
     /// Enable or disable the timer interrupt.
@@ -88,6 +415,34 @@ where
         self.clear_register_bit_flag(Register::CTRL_STATUS_2, BitFlags::TF)
     }
 
+    /// Check the timer flag and, if set, clear it, for `nb`-style polling, e.g. with `nb::block!`.
+    ///
+    /// Returns `WouldBlock` until the timer flag is set.
+    pub fn poll_timer(&mut self) -> nb::Result<(), Error<E>> {
+        if !self.get_timer_flag()? {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.clear_timer_flag()?;
+        Ok(())
+    }
+
+    /// Wait until the timer flag is set, polling it at the given interval, and clear it.
+    ///
+    /// For applications not using the interrupt pin. Blocks forever if the timer is not running.
+    pub fn wait_for_timer<D>(
+        &mut self,
+        delay: &mut D,
+        poll_interval_ms: u32,
+    ) -> Result<(), Error<E>>
+    where
+        D: DelayMs<u32>,
+    {
+        while !self.get_timer_flag()? {
+            delay.delay_ms(poll_interval_ms);
+        }
+        self.clear_timer_flag()
+    }
+
     /// Select the interrupt output mode when TF flag is set (continuous or pulsating).
     pub fn timer_interrupt_output(&mut self, output: InterruptOutput) -> Result<(), Error<E>> {
         match output {
@@ -109,20 +464,591 @@ where
         Ok(data[0])
     }
 
-    //TO DO:
-    //
-    // pub fn get_timer_interrupt_output()
-    //
-    // pub fn get_timer_frequency()
-    //
-    /* USE THIS EXAMPLE FOR GET_TIMER_FREQUENCY() ?
+    /// Read the interrupt output mode when TF flag is set (continuous or pulsating).
+    pub fn get_timer_interrupt_output(&mut self) -> Result<InterruptOutput, Error<E>> {
+        if self.is_register_bit_flag_high(Register::CTRL_STATUS_2, BitFlags::TI_TP)? {
+            Ok(InterruptOutput::Pulsating)
+        } else {
+            Ok(InterruptOutput::Continuous)
+        }
+    }
+
+    /// Read the timer frequency.
+    pub fn get_timer_frequency(&mut self) -> Result<TimerFreq, Error<E>> {
+        let data = self.read_register(Register::TIMER_CTRL)?;
+        Ok(TimerFreq::from_bits(data))
+    }
 
-    pub fn get_square_wave_output_rate(&mut self) -> Result<SQWOUTRateBits, Error<E>> {
-        let data = self.read_register(Register::SQWOUT)?;
-        Ok(SQWOUTRateBits {
-            rs0: (data & BitFlags::OUTRATERS0) != 0,
-            rs1: (data & BitFlags::OUTRATERS1) != 0,
+    /// Write the whole timer configuration in two transactions: the control register
+    /// then the timer control and timer registers. The timer mode follows the output mode.
+    ///
+    /// The alarm interrupt setting and both flags are kept.
+    pub fn set_timer_config(&mut self, config: &TimerConfig) -> Result<(), Error<E>> {
+        let data = self.read_register(Register::CTRL_STATUS_2)?;
+        let mut data = (data & BitFlags::AIE) | BitFlags::AF | BitFlags::TF;
+        if config.interrupt {
+            data |= BitFlags::TIE;
+        }
+        if config.pulse_mode {
+            data |= BitFlags::TI_TP;
+        }
+        self.write_register(Register::CTRL_STATUS_2, data)?;
+        let mut control = config.frequency.bits();
+        if config.enabled {
+            control |= BitFlags::TE;
+        }
+        self.write_registers(Register::TIMER_CTRL, &[control, config.ticks])?;
+        self.timer_mode = if config.pulse_mode {
+            TimerMode::Periodic
+        } else {
+            TimerMode::OneShot
+        };
+        Ok(())
+    }
+
+    /// Read the whole timer configuration in two transactions.
+    pub fn get_timer_config(&mut self) -> Result<TimerConfig, Error<E>> {
+        Ok(self.timer_state()?.config)
+    }
+
+    /// Read the timer configuration and flag in two transactions, e.g. for debugging.
+    pub fn timer_state(&mut self) -> Result<TimerState, Error<E>> {
+        let status = self.read_register(Register::CTRL_STATUS_2)?;
+        let mut data = [0; 2];
+        self.read_registers(Register::TIMER_CTRL, &mut data)?;
+        Ok(TimerState {
+            config: TimerConfig {
+                enabled: data[0] & BitFlags::TE != 0,
+                frequency: TimerFreq::from_bits(data[0]),
+                ticks: data[1],
+                interrupt: status & BitFlags::TIE != 0,
+                pulse_mode: status & BitFlags::TI_TP != 0,
+            },
+            flag: status & BitFlags::TF != 0,
         })
     }
-    */
+
+    /// Read the remaining time of the countdown, from the timer value and frequency
+    /// read in one transaction.
+    pub fn get_remaining_time(&mut self) -> Result<Duration, Error<E>> {
+        let mut data = [0; 2];
+        self.read_registers(Register::TIMER_CTRL, &mut data)?;
+        Ok(TimerFreq::from_bits(data[0]).duration(data[1]))
+    }
+}
+
+/// Handler of the timer events, decoupling what to do on each event from checking
+/// and clearing the timer flag.
+///
+/// ```rust
+/// # use pcf8563::*;
+/// # let i2c = embedded_hal_mock::I2cMock::new();
+/// # let mut rtc = PCF8563::new(i2c);
+/// let mut ticks = 0;
+/// let mut dispatcher = TimerDispatcher::new(|| ticks += 1);
+/// // e.g. in the interrupt handler
+/// dispatcher.service(&mut rtc).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct TimerDispatcher<F> {
+    handler: F,
+}
+
+impl<F> TimerDispatcher<F>
+where
+    F: FnMut(),
+{
+    /// Create a new dispatcher calling the handler on each timer event.
+    pub fn new(handler: F) -> Self {
+        TimerDispatcher { handler }
+    }
+
+    /// Service the timer with `service_timer()`, calling the handler if the timer flag was set.
+    ///
+    /// Returns whether the timer flag was set.
+    pub fn service<I2C, E>(&mut self, rtc: &mut PCF8563<I2C>) -> Result<bool, Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        let triggered = rtc.service_timer()?;
+        if triggered {
+            (self.handler)();
+        }
+        Ok(triggered)
+    }
+
+    /// Destroy the dispatcher, return the handler.
+    pub fn destroy(self) -> F {
+        self.handler
+    }
+}
+
+/// Countdown longer than the timer can hold, chaining timer periods of up to 255 ticks.
+///
+/// Durations up to 255 minutes use a single period at the finest frequency which can hold them,
+/// longer ones are counted in minutes. The remaining periods are tracked by `service()`,
+/// which must be called when the timer flag is set, e.g. from the interrupt handler.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LongTimer {
+    frequency: TimerFreq,
+    ticks: u32,
+    pending: u32,
+}
+
+impl LongTimer {
+    /// Create a new countdown for the duration, rounded to the nearest tick.
+    ///
+    /// Will return an `OutOfRangeError` if the duration is zero or too long.
+    pub fn new(duration: Duration) -> Result<Self, OutOfRangeError> {
+        if duration == Duration::from_secs(0) {
+            return Err(OutOfRangeError);
+        }
+        let (frequency, ticks) = match timer_setting(duration) {
+            Some((frequency, ticks)) => (frequency, u128::from(ticks)),
+            None => (
+                TimerFreq::Timer_1_60Hz,
+                TimerFreq::Timer_1_60Hz.ticks(duration),
+            ),
+        };
+        if ticks > u128::from(u32::MAX) {
+            return Err(OutOfRangeError);
+        }
+        Ok(LongTimer {
+            frequency,
+            ticks: ticks as u32,
+            pending: 0,
+        })
+    }
+
+    /// Duration of the countdown.
+    pub fn duration(&self) -> Duration {
+        self.frequency.resolution() * self.ticks
+    }
+
+    /// Clear the timer flag, start the first period and enable the timer interrupt.
+    pub fn start<I2C, E>(&mut self, rtc: &mut PCF8563<I2C>) -> Result<(), Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        rtc.stop_and_clear_timer(false)?;
+        rtc.set_timer_mode(TimerMode::OneShot)?;
+        let chunk = self.ticks.min(255);
+        self.pending = self.ticks - chunk;
+        rtc.start_timer(self.frequency, chunk as u8)?;
+        rtc.control_timer_interrupt(Control::On)
+    }
+
+    /// Check the timer flag and, if set, clear it and continue with the next period.
+    ///
+    /// Returns whether the whole countdown expired, in which case the timer is stopped.
+    /// The timer reloads by itself, so shortening the last period happens after the reload,
+    /// delayed by the time taken to service the flag.
+    pub fn service<I2C, E>(&mut self, rtc: &mut PCF8563<I2C>) -> Result<bool, Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        if !rtc.get_timer_flag()? {
+            return Ok(false);
+        }
+        if self.pending == 0 {
+            rtc.stop_and_clear_timer(false)?;
+            return Ok(true);
+        }
+        rtc.clear_timer_flag()?;
+        let chunk = self.pending.min(255);
+        self.pending -= chunk;
+        if chunk < 255 {
+            rtc.set_timer(chunk as u8)?;
+        }
+        Ok(false)
+    }
+
+    /// Read the remaining time of the whole countdown.
+    pub fn remaining<I2C, E>(&self, rtc: &mut PCF8563<I2C>) -> Result<Duration, Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        Ok(rtc.get_remaining_time()? + self.frequency.resolution() * self.pending)
+    }
+
+    /// Stop the timer, clear the timer flag and disable the timer interrupt.
+    pub fn cancel<I2C, E>(&mut self, rtc: &mut PCF8563<I2C>) -> Result<(), Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        self.pending = 0;
+        rtc.stop_and_clear_timer(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::{DelayMockNoop, I2cMock};
+
+    #[test]
+    fn cannot_set_frequency_of_running_timer() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x82]);
+        let mut rtc = PCF8563::new(i2c);
+        assert!(matches!(
+            rtc.set_timer_frequency(TimerFreq::Timer_64Hz),
+            Err(Error::TimerRunning)
+        ));
+    }
+
+    #[test]
+    fn set_timer_frequency_replaces_previous_frequency() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x03]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.set_timer_frequency(TimerFreq::Timer_4096Hz).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::TIMER_CTRL, 0x00]);
+    }
+
+    #[test]
+    fn can_set_timer_duration() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        let actual = rtc.set_timer_duration(Duration::from_millis(50)).unwrap();
+        assert_eq!(actual.as_micros(), 50_048);
+        let i2c = rtc.destroy();
+        // 205 ticks at 4096 Hz
+        assert_eq!(i2c.get_write_data(), &[Register::TIMER, 205]);
+
+        let mut rtc = PCF8563::new(i2c);
+        let actual = rtc.set_timer_duration(Duration::from_secs(300)).unwrap();
+        assert_eq!(actual, Duration::from_secs(300));
+        let i2c = rtc.destroy();
+        // 5 ticks at 1/60 Hz
+        assert_eq!(i2c.get_write_data(), &[Register::TIMER, 5]);
+    }
+
+    #[test]
+    fn cannot_set_timer_duration_out_of_range() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        assert!(matches!(
+            rtc.set_timer_duration(Duration::from_secs(0)),
+            Err(Error::InvalidInputData)
+        ));
+        assert!(matches!(
+            rtc.set_timer_duration(Duration::from_secs(256 * 60)),
+            Err(Error::InvalidInputData)
+        ));
+    }
+
+    #[test]
+    fn can_start_periodic_timer() {
+        let mut i2c = I2cMock::new();
+        // AIE and TF set
+        i2c.set_read_data(&[0x06]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.start_periodic(TimerFreq::Timer_1Hz, 10).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::TIMER_CTRL, 0x82, 10]);
+    }
+
+    #[test]
+    fn service_stops_one_shot_timer() {
+        let mut i2c = I2cMock::new();
+        // TF set, timer enabled at 1 Hz, TF set
+        i2c.set_read_data(&[0x04, 0x82, 0x04]);
+        let mut rtc = PCF8563::new(i2c);
+        assert!(rtc.service_timer().unwrap());
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x08]);
+    }
+
+    #[test]
+    fn service_keeps_periodic_timer_running() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x00, 0x04]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.set_timer_mode(TimerMode::Periodic).unwrap();
+        assert_eq!(rtc.get_timer_mode(), TimerMode::Periodic);
+        let mut i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x1c]);
+        i2c.set_read_data(&[0x14, 0x14]);
+        let mut rtc = PCF8563 {
+            timer_mode: TimerMode::Periodic,
+            ..PCF8563::new(i2c)
+        };
+        assert!(rtc.service_timer().unwrap());
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x18]);
+    }
+
+    #[test]
+    fn can_enable_minute_interrupt() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        rtc.enable_minute_interrupt().unwrap();
+        assert_eq!(rtc.get_timer_mode(), TimerMode::Periodic);
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::TIMER_CTRL, 0x83, 1]);
+    }
+
+    #[test]
+    fn can_enable_second_interrupt() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        rtc.enable_second_interrupt().unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::TIMER_CTRL, 0x82, 1]);
+    }
+
+    #[test]
+    fn can_get_remaining_time() {
+        let mut i2c = I2cMock::new();
+        // 32 ticks at 64 Hz
+        i2c.set_read_data(&[0x81, 32]);
+        let mut rtc = PCF8563::new(i2c);
+        assert_eq!(
+            rtc.get_remaining_time().unwrap(),
+            Duration::from_millis(500)
+        );
+    }
+
+    #[test]
+    fn can_start_timer() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        rtc.start_timer(TimerFreq::Timer_64Hz, 100).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::TIMER_CTRL, 0x81]);
+    }
+
+    #[test]
+    fn can_set_and_get_timer_config() {
+        let config = TimerConfig {
+            enabled: true,
+            frequency: TimerFreq::Timer_64Hz,
+            ticks: 32,
+            interrupt: true,
+            pulse_mode: true,
+        };
+        let mut i2c = I2cMock::new();
+        // AIE set, then the written registers
+        i2c.set_read_data(&[0x02, 0x13, 0x81, 0x20]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.set_timer_config(&config).unwrap();
+        assert_eq!(rtc.get_timer_mode(), TimerMode::Periodic);
+        assert_eq!(rtc.get_timer_config().unwrap(), config);
+        let mut rtc = PCF8563::new(rtc.destroy());
+        rtc.set_timer_config(&config).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::TIMER_CTRL, 0x81, 32]);
+    }
+
+    #[test]
+    fn can_measure_clock_error() {
+        let mut i2c = I2cMock::new();
+        // timer stopped, then TF set twice
+        i2c.set_read_data(&[0x00, 0x00, 0x04, 0x04, 0x04, 0x04]);
+        let mut rtc = PCF8563::new(i2c);
+        let mut counter = [1_000, 1_001_050].iter().copied();
+        let error = rtc
+            .measure_clock_error(TimerFreq::Timer_1Hz, 1, 1_000_000, || {
+                counter.next().unwrap()
+            })
+            .unwrap();
+        assert_eq!(error, 50);
+    }
+
+    #[test]
+    fn clock_error_is_checked_and_saturated() {
+        let mut rtc = PCF8563::new(I2cMock::new());
+        assert!(matches!(
+            rtc.measure_clock_error(TimerFreq::Timer_4096Hz, 1, 1, || 0),
+            Err(Error::InvalidInputData)
+        ));
+        let mut i2c = I2cMock::new();
+        // timer stopped, then TF set twice
+        i2c.set_read_data(&[0x00, 0x00, 0x04, 0x04, 0x04, 0x04]);
+        let mut rtc = PCF8563::new(i2c);
+        let mut counter = [0, u32::MAX].iter().copied();
+        let error = rtc
+            .measure_clock_error(TimerFreq::Timer_1_60Hz, 1, 1, || counter.next().unwrap())
+            .unwrap();
+        assert_eq!(error, i32::MAX);
+    }
+
+    #[test]
+    fn can_get_resolution_and_range() {
+        const RESOLUTION: Duration = TimerFreq::Timer_64Hz.resolution();
+        assert_eq!(RESOLUTION, Duration::from_micros(15_625));
+        assert_eq!(
+            TimerFreq::Timer_4096Hz.max_duration(),
+            Duration::from_nanos(62_255_859)
+        );
+        assert_eq!(
+            TimerFreq::Timer_1_60Hz.max_duration(),
+            Duration::from_secs(255 * 60)
+        );
+    }
+
+    #[test]
+    fn can_chain_long_timer_periods() {
+        let mut timer = LongTimer::new(Duration::from_secs(600 * 60)).unwrap();
+        assert_eq!(timer.duration(), Duration::from_secs(600 * 60));
+        let mut rtc = PCF8563::new(I2cMock::new());
+        timer.start(&mut rtc).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x0d]);
+
+        let mut i2c = i2c;
+        // 1/60 Hz and 200 minutes left in the second period
+        i2c.set_read_data(&[0x04, 0x04, 0x83, 200]);
+        let mut rtc = PCF8563::new(i2c);
+        assert!(!timer.service(&mut rtc).unwrap());
+        assert_eq!(
+            timer.remaining(&mut rtc).unwrap(),
+            Duration::from_secs((200 + 90) * 60)
+        );
+
+        let mut i2c = rtc.destroy();
+        i2c.set_read_data(&[0x04, 0x04]);
+        let mut rtc = PCF8563::new(i2c);
+        assert!(!timer.service(&mut rtc).unwrap());
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::TIMER, 90]);
+
+        let mut i2c = i2c;
+        i2c.set_read_data(&[0x04, 0x83, 0x04]);
+        let mut rtc = PCF8563::new(i2c);
+        assert!(timer.service(&mut rtc).unwrap());
+    }
+
+    #[test]
+    fn cannot_create_empty_long_timer() {
+        assert_eq!(LongTimer::new(Duration::from_secs(0)), Err(OutOfRangeError));
+    }
+
+    #[test]
+    fn can_read_timer_state() {
+        let mut i2c = I2cMock::new();
+        // TF and TIE set, timer running at 1 Hz with 7 ticks left
+        i2c.set_read_data(&[0x05, 0x82, 0x07]);
+        let mut rtc = PCF8563::new(i2c);
+        let state = rtc.timer_state().unwrap();
+        assert!(state.flag);
+        assert_eq!(
+            state.config,
+            TimerConfig {
+                enabled: true,
+                frequency: TimerFreq::Timer_1Hz,
+                ticks: 7,
+                interrupt: true,
+                pulse_mode: false,
+            }
+        );
+    }
+
+    #[test]
+    fn can_poll_timer() {
+        let mut i2c = I2cMock::new();
+        // TF not set, then set
+        i2c.set_read_data(&[0x00, 0x04, 0x04]);
+        let mut rtc = PCF8563::new(i2c);
+        assert!(matches!(rtc.poll_timer(), Err(nb::Error::WouldBlock)));
+        assert!(rtc.poll_timer().is_ok());
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x08]);
+    }
+
+    #[test]
+    fn dispatcher_calls_handler_on_timer_flag() {
+        let mut i2c = I2cMock::new();
+        // TF not set, then set
+        i2c.set_read_data(&[0x00, 0x04, 0x00, 0x04]);
+        let mut rtc = PCF8563::new(i2c);
+        let mut ticks = 0;
+        let mut dispatcher = TimerDispatcher::new(|| ticks += 1);
+        assert!(!dispatcher.service(&mut rtc).unwrap());
+        assert!(dispatcher.service(&mut rtc).unwrap());
+        assert_eq!(ticks, 1);
+    }
+
+    #[test]
+    fn can_compute_quantization() {
+        let quantization = Quantization::timer(Duration::from_millis(50)).unwrap();
+        assert_eq!(quantization.actual.as_micros(), 50_048);
+        assert_eq!(quantization.error().as_micros(), 48);
+        assert_eq!(
+            Quantization::timer(Duration::from_secs(256 * 60)),
+            Err(OutOfRangeError)
+        );
+        let now = DateTime {
+            year: 21,
+            month: 4,
+            day: 30,
+            weekday: 5,
+            hours: 12,
+            minutes: 0,
+            seconds: 20,
+        };
+        let quantization = Quantization::alarm_in(&now, Duration::from_secs(90)).unwrap();
+        assert_eq!(quantization.actual, Duration::from_secs(100));
+        assert_eq!(quantization.error(), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn alarm_quantization_uses_year_mapping() {
+        let now = DateTime {
+            year: 0,
+            month: 2,
+            day: 1,
+            weekday: 2,
+            hours: 12,
+            minutes: 0,
+            seconds: 30,
+        };
+        let duration = Duration::from_secs(28 * 86_400);
+        // 2000-02-29 in a leap year
+        assert!(Quantization::alarm_in(&now, duration).is_ok());
+        // 2100-03-01, the day alarm would match on 2100-02-01
+        let mapping = YearMapping::new(2100, 100).unwrap();
+        assert_eq!(
+            Quantization::alarm_in_with(&now, duration, mapping),
+            Err(OutOfRangeError)
+        );
+    }
+
+    #[test]
+    fn can_stop_and_clear_timer() {
+        let mut i2c = I2cMock::new();
+        // timer enabled, then AIE, TF and TIE set
+        i2c.set_read_data(&[0x82, 0x07]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.stop_and_clear_timer(true).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x0a]);
+    }
+
+    #[test]
+    fn can_get_timer_frequency() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x82]);
+        let mut rtc = PCF8563::new(i2c);
+        assert_eq!(rtc.get_timer_frequency().unwrap(), TimerFreq::Timer_1Hz);
+    }
+
+    #[test]
+    fn can_get_timer_interrupt_output() {
+        let mut i2c = I2cMock::new();
+        i2c.set_read_data(&[0x11]);
+        let mut rtc = PCF8563::new(i2c);
+        assert_eq!(
+            rtc.get_timer_interrupt_output().unwrap(),
+            InterruptOutput::Pulsating
+        );
+    }
+
+    #[test]
+    fn can_wait_for_timer() {
+        let mut i2c = I2cMock::new();
+        // TF set on the second poll
+        i2c.set_read_data(&[0x01, 0x05, 0x05]);
+        let mut rtc = PCF8563::new(i2c);
+        rtc.wait_for_timer(&mut DelayMockNoop, 10).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::CTRL_STATUS_2, 0x09]);
+    }
 }
@@ -0,0 +1,295 @@
+//! Time zones given as a POSIX TZ string, e.g. `CET-1CEST,M3.5.0,M10.5.0/3`.
+//!
+//! The string holds the standard time zone name and offset, optionally followed by the
+//! daylight saving time zone name, offset and rules: `std offset [dst [offset] [,start,end]]`.
+//! The offsets are `[+|-]hh[:mm[:ss]]`, positive west of Greenwich (`EST5` is UTC-05:00),
+//! and the names are either alphabetic or quoted with `<>`, e.g. `<+0330>-3:30`.
+//!
+//! Only the rules of the form `Mm.w.d[/time]` (day `d` of week `w` of month `m`, at the local
+//! time `time`, 02:00 by default) are supported, which is what the time zones in use today need.
+//! Without rules, the rules of the United States are taken, as with glibc.
+//! The seconds of the offsets and times are ignored.
+//!
+//! ```rust
+//! # use pcf8563::*;
+//! # let i2c = embedded_hal_mock::I2cMock::new();
+//! # let rtc = PCF8563::new(i2c);
+//! let tz: PosixTz = "CET-1CEST,M3.5.0,M10.5.0/3".parse().unwrap();
+//! let mut clock = LocalClock::with_tz(rtc, &tz);
+//! ```
+
+use super::{hal, DstRules, LocalClock, Transition, TransitionClock, PCF8563};
+use core::convert::TryFrom;
+use core::str::FromStr;
+use hal::blocking::i2c::{Write, WriteRead};
+
+/// Default time of the transitions, 02:00 local time.
+const DEFAULT_TRANSITION_MINUTES: i16 = 120;
+
+/// Error returned when a POSIX TZ string can't be parsed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TzParseError;
+
+/// Time zone parsed from a POSIX TZ string, see the module documentation for the syntax.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PosixTz {
+    /// Standard offset from UTC in minutes, positive east of Greenwich (e.g. 60 for UTC+01:00).
+    pub offset_minutes: i16,
+    /// Daylight saving time rules, if any.
+    pub dst: Option<DstRules>,
+}
+
+impl FromStr for PosixTz {
+    type Err = TzParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            bytes: s.as_bytes(),
+            pos: 0,
+        };
+        parser.name()?;
+        let standard = parser.offset()?;
+        if parser.is_done() {
+            return Ok(PosixTz {
+                offset_minutes: -standard,
+                dst: None,
+            });
+        }
+        parser.name()?;
+        let daylight = if parser.is_done() || parser.peek() == Some(b',') {
+            standard - 60
+        } else {
+            parser.offset()?
+        };
+        let save_minutes = standard - daylight;
+        let (start, end) = if parser.is_done() {
+            (DstRules::US.start, DstRules::US.end)
+        } else {
+            parser.expect(b',')?;
+            let start = parser.rule()?;
+            parser.expect(b',')?;
+            (start, parser.rule()?)
+        };
+        if !parser.is_done() {
+            return Err(TzParseError);
+        }
+        Ok(PosixTz {
+            offset_minutes: -standard,
+            dst: Some(DstRules {
+                start,
+                end,
+                save_minutes,
+            }),
+        })
+    }
+}
+
+impl<I2C, E> LocalClock<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Create a new local clock in the time zone.
+    pub fn with_tz(rtc: PCF8563<I2C>, tz: &PosixTz) -> Self {
+        let mut clock = LocalClock::new(rtc, tz.offset_minutes);
+        clock.set_dst_rules(tz.dst);
+        clock
+    }
+
+    /// Change the time zone, i.e. the standard offset and the daylight saving time rules.
+    pub fn set_tz(&mut self, tz: &PosixTz) {
+        self.set_offset_minutes(tz.offset_minutes);
+        self.set_dst_rules(tz.dst);
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn is_done(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), TzParseError> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(TzParseError)
+        }
+    }
+
+    /// Time zone name, at least three characters, alphabetic or quoted with `<>`.
+    fn name(&mut self) -> Result<(), TzParseError> {
+        let start = self.pos;
+        if self.eat(b'<') {
+            while matches!(
+                self.peek(),
+                Some(b'0'..=b'9' | b'A'..=b'Z' | b'a'..=b'z' | b'+' | b'-')
+            ) {
+                self.pos += 1;
+            }
+            let len = self.pos - start - 1;
+            self.expect(b'>')?;
+            return if len >= 3 { Ok(()) } else { Err(TzParseError) };
+        }
+        while matches!(self.peek(), Some(b'A'..=b'Z' | b'a'..=b'z')) {
+            self.pos += 1;
+        }
+        if self.pos - start >= 3 {
+            Ok(())
+        } else {
+            Err(TzParseError)
+        }
+    }
+
+    /// Decimal number of at most three digits.
+    fn number(&mut self) -> Result<i16, TzParseError> {
+        let start = self.pos;
+        let mut value = 0;
+        while let Some(digit @ b'0'..=b'9') = self.peek() {
+            if self.pos - start == 3 {
+                return Err(TzParseError);
+            }
+            value = value * 10 + i16::from(digit - b'0');
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(TzParseError);
+        }
+        Ok(value)
+    }
+
+    /// `[+|-]hh[:mm[:ss]]` in minutes, the seconds are ignored.
+    fn offset(&mut self) -> Result<i16, TzParseError> {
+        let negative = self.eat(b'-');
+        if !negative {
+            self.eat(b'+');
+        }
+        let hours = self.number()?;
+        let mut minutes = 0;
+        if self.eat(b':') {
+            minutes = self.number()?;
+            if self.eat(b':') {
+                self.number()?;
+            }
+        }
+        if hours > 167 || minutes > 59 {
+            return Err(TzParseError);
+        }
+        let minutes = hours * 60 + minutes;
+        Ok(if negative { -minutes } else { minutes })
+    }
+
+    /// `Mm.w.d[/time]`
+    fn rule(&mut self) -> Result<Transition, TzParseError> {
+        self.expect(b'M')?;
+        let month = self.number()?;
+        self.expect(b'.')?;
+        let week = self.number()?;
+        self.expect(b'.')?;
+        let weekday = self.number()?;
+        if !(1..=12).contains(&month) || !(1..=5).contains(&week) || weekday > 6 {
+            return Err(TzParseError);
+        }
+        let minutes = if self.eat(b'/') {
+            self.offset()?
+        } else {
+            DEFAULT_TRANSITION_MINUTES
+        };
+        let narrow = |value: i16| u8::try_from(value).map_err(|_| TzParseError);
+        Ok(Transition {
+            month: narrow(month)?,
+            week: narrow(week)?,
+            weekday: narrow(weekday)?,
+            minutes,
+            clock: TransitionClock::Local,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::I2cMock;
+
+    #[test]
+    fn can_parse_central_european_time() {
+        let tz: PosixTz = "CET-1CEST,M3.5.0,M10.5.0/3".parse().unwrap();
+        assert_eq!(tz.offset_minutes, 60);
+        let rules = tz.dst.unwrap();
+        assert_eq!(rules.save_minutes, 60);
+        assert_eq!(
+            rules.start,
+            Transition {
+                month: 3,
+                week: 5,
+                weekday: 0,
+                minutes: 120,
+                clock: TransitionClock::Local,
+            }
+        );
+        assert_eq!(rules.end.month, 10);
+        assert_eq!(rules.end.minutes, 180);
+    }
+
+    #[test]
+    fn reads_local_time_in_time_zone() {
+        let mut i2c = I2cMock::new();
+        // 2021-07-01 10:00:00 UTC
+        i2c.set_read_data(&[0x00, 0x00, 0x10, 0x01, 0x04, 0x07, 0x21]);
+        let tz: PosixTz = "CET-1CEST,M3.5.0,M10.5.0/3".parse().unwrap();
+        let mut clock = LocalClock::with_tz(PCF8563::new(i2c), &tz);
+        assert_eq!(clock.get_datetime().unwrap().hours, 12);
+    }
+
+    #[test]
+    fn can_parse_zones_without_rules() {
+        let tz: PosixTz = "EST5EDT".parse().unwrap();
+        assert_eq!(tz.offset_minutes, -300);
+        assert_eq!(tz.dst, Some(DstRules::US));
+        let tz: PosixTz = "<+0330>-3:30".parse().unwrap();
+        assert_eq!(tz.offset_minutes, 210);
+        assert_eq!(tz.dst, None);
+        let tz: PosixTz = "UTC0".parse().unwrap();
+        assert_eq!(tz.offset_minutes, 0);
+    }
+
+    #[test]
+    fn can_parse_negative_daylight_saving_time() {
+        let tz: PosixTz = "IST-1GMT0,M10.5.0,M3.5.0/1".parse().unwrap();
+        assert_eq!(tz.offset_minutes, 60);
+        assert_eq!(tz.dst.unwrap().save_minutes, -60);
+    }
+
+    #[test]
+    fn cannot_parse_invalid_strings() {
+        for tz in [
+            "",
+            "CET",
+            "CE-1",
+            "CET-1CEST,M3.5.0",
+            "CET-1CEST,M13.5.0,M10.5.0/3",
+            "CET-1CEST,M3.6.0,M10.5.0/3",
+            "CET-1CEST,J60,J300",
+            "CET-1CEST,M3.5.0,M10.5.0/3,",
+            "<+03-3",
+        ] {
+            assert_eq!(tz.parse::<PosixTz>(), Err(TzParseError));
+        }
+    }
+}
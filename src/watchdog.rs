@@ -0,0 +1,98 @@
+//! External watchdog using the timer and the interrupt pin.
+//!
+//! Once started, the timer counts down and the interrupt pin goes low when it expires,
+//! e.g. to reset the microcontroller or cut its power. `feed()` reloads the timer value
+//! before it expires. The interrupt stays active until the timer flag is cleared.
+
+use super::{hal, BitFlags, Error, OutOfRangeError, Register, TimerFreq, PCF8563};
+use core::time::Duration;
+use hal::blocking::i2c::{Write, WriteRead};
+
+/// Watchdog expiring after a number of timer ticks without being fed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RtcWatchdog {
+    frequency: TimerFreq,
+    ticks: u8,
+}
+
+impl RtcWatchdog {
+    /// Create a new watchdog expiring after the number of ticks [1-255] at the frequency.
+    ///
+    /// Will return an `OutOfRangeError` if the number of ticks is zero.
+    pub fn new(frequency: TimerFreq, ticks: u8) -> Result<Self, OutOfRangeError> {
+        if ticks == 0 {
+            return Err(OutOfRangeError);
+        }
+        Ok(RtcWatchdog { frequency, ticks })
+    }
+
+    /// Time after which the watchdog expires if not fed.
+    pub fn timeout(&self) -> Duration {
+        self.frequency.duration(self.ticks)
+    }
+
+    /// Clear the timer flag, enable the timer interrupt in continuous mode and start the timer.
+    ///
+    /// The alarm interrupt setting and flag are kept.
+    pub fn start<I2C, E>(&self, rtc: &mut PCF8563<I2C>) -> Result<(), Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        let data = rtc.read_register(Register::CTRL_STATUS_2)?;
+        let data = (data & BitFlags::AIE) | BitFlags::AF | BitFlags::TIE;
+        rtc.write_register(Register::CTRL_STATUS_2, data)?;
+        rtc.start_timer(self.frequency, self.ticks)
+    }
+
+    /// Reload the timer value, with a single write.
+    pub fn feed<I2C, E>(&self, rtc: &mut PCF8563<I2C>) -> Result<(), Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        rtc.set_timer(self.ticks)
+    }
+
+    /// Check if the watchdog expired, i.e. the timer flag is set.
+    pub fn has_expired<I2C, E>(&self, rtc: &mut PCF8563<I2C>) -> Result<bool, Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        rtc.get_timer_flag()
+    }
+
+    /// Stop the timer, clear the timer flag and disable the timer interrupt.
+    pub fn stop<I2C, E>(&self, rtc: &mut PCF8563<I2C>) -> Result<(), Error<E>>
+    where
+        I2C: Write<Error = E> + WriteRead<Error = E>,
+    {
+        rtc.stop_and_clear_timer(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::I2cMock;
+
+    #[test]
+    fn cannot_create_watchdog_without_ticks() {
+        assert_eq!(
+            RtcWatchdog::new(TimerFreq::Timer_1Hz, 0),
+            Err(OutOfRangeError)
+        );
+    }
+
+    #[test]
+    fn can_start_and_feed_watchdog() {
+        let watchdog = RtcWatchdog::new(TimerFreq::Timer_1Hz, 30).unwrap();
+        assert_eq!(watchdog.timeout(), Duration::from_secs(30));
+        let mut i2c = I2cMock::new();
+        // AIE, TF and TI_TP set
+        i2c.set_read_data(&[0x16]);
+        let mut rtc = PCF8563::new(i2c);
+        watchdog.start(&mut rtc).unwrap();
+        watchdog.feed(&mut rtc).unwrap();
+        let i2c = rtc.destroy();
+        assert_eq!(i2c.get_write_data(), &[Register::TIMER, 30]);
+    }
+}